@@ -1,9 +1,11 @@
 use std::path::PathBuf;
 
+use clap::error::ErrorKind;
 use clap::{Command, CommandFactory, Parser, Subcommand};
 use clap_complete::{Shell, generate};
 use std::io::{self, Write};
 
+use crate::commands::tmp::TmpCommands;
 use crate::{commands, helpers::template::render_template};
 
 #[derive(Subcommand, Debug)]
@@ -11,14 +13,50 @@ pub enum ECommands {
     #[command(about = "Add a new repository")]
     Add {
         url: String,
+        #[arg(
+            long = "base",
+            help = "Base dir to clone into, overrides the routing rules in config"
+        )]
+        base: Option<String>,
         #[arg(allow_hyphen_values = true)]
         rest: Vec<String>,
     },
-    #[command(about = "Find a repository by keyword")]
+    #[command(about = "Find a repository by keywords")]
     Find {
-        keyword: String,
+        #[arg(
+            required = true,
+            allow_hyphen_values = true,
+            help = "Keywords or query terms that must all match, e.g. `api owner:acme -tag:archived`"
+        )]
+        keywords: Vec<String>,
         #[arg(short = 'q', long = "query", help = "Only query result")]
         query: bool,
+        #[arg(
+            long = "checkout",
+            help = "For a branch, file or pull request URL, switch to the branch it refers to"
+        )]
+        checkout: bool,
+        #[arg(long = "pick", help = "Show the chooser even when a choice was remembered")]
+        pick: bool,
+        #[arg(long = "submodule", help = "Open a submodule of the repository that is found")]
+        submodule: bool,
+    },
+    #[command(about = "Forget the choices remembered for ambiguous keywords")]
+    Forget {
+        #[arg(allow_hyphen_values = true, help = "Only forget the choice for these keywords")]
+        keywords: Vec<String>,
+    },
+    #[command(about = "Complete a path inside a repository", hide = true)]
+    Complete {
+        #[arg(
+            allow_hyphen_values = true,
+            help = "Arguments given to `p`, the last one is completed"
+        )]
+        words: Vec<String>,
+    },
+    #[command(about = "Record a visit to the repository containing a path", hide = true)]
+    Visit {
+        path: PathBuf,
     },
     #[command(about = "Sync repositories")]
     Sync,
@@ -42,19 +80,74 @@ pub enum ECommands {
         yes: bool,
     },
     #[command(about = "List all repositories")]
-    List,
+    List {
+        #[arg(
+            allow_hyphen_values = true,
+            help = "Only list repositories matching this query, e.g. `owner:acme -tag:archived`"
+        )]
+        query: Vec<String>,
+        #[arg(
+            short = 't',
+            long = "tag",
+            value_delimiter = ',',
+            help = "Only list repositories with all of these tags"
+        )]
+        tags: Vec<String>,
+    },
     #[command(about = "Check for repositories with uncommitted or unpushed changes")]
     Check {
+        #[arg(
+            allow_hyphen_values = true,
+            help = "Only check repositories matching this query, e.g. `host:gitlab.com`"
+        )]
+        query: Vec<String>,
+        #[arg(
+            short = 't',
+            long = "tag",
+            value_delimiter = ',',
+            help = "Only check repositories with all of these tags"
+        )]
+        tags: Vec<String>,
         #[arg(
             short = 'd',
             long = "dirty-only",
             help = "Only report repositories with uncommitted changes"
         )]
         dirty_only: bool,
-        #[arg(long = "json", help = "Output the result as JSON")]
+        #[arg(long = "json", hide = true, help = "Deprecated alias of `--format json`")]
         json: bool,
     },
+    #[command(about = "Find repositories that are not where their remote says they should be")]
+    Doctor {
+        #[arg(long = "fix", help = "Move misplaced repositories and drop stale records")]
+        fix: bool,
+    },
     Tmp(commands::tmp::TmpArgs),
+    #[command(about = "Manage repository tags")]
+    Tag(commands::tag::TagArgs),
+    #[command(about = "Show or set the note of a repository")]
+    Note {
+        #[arg(help = "Repository path or keyword")]
+        repo: String,
+        #[arg(help = "Note text, omit to show the current note")]
+        text: Option<String>,
+        #[arg(long = "clear", help = "Remove the note", conflicts_with = "text")]
+        clear: bool,
+    },
+    #[command(about = "Print a workspace manifest of all repositories")]
+    Export,
+    #[command(about = "Clone the repositories listed in a workspace manifest")]
+    Apply {
+        path: PathBuf,
+        #[arg(long = "dry-run", help = "Only show what would be cloned")]
+        dry_run: bool,
+    },
+    #[command(about = "Maintain the repository index")]
+    Db(commands::db::DbArgs),
+    #[command(about = "Manage git worktrees")]
+    Wt(commands::wt::WtArgs),
+    #[command(about = "Keep the index up to date by watching the base dirs")]
+    Daemon(commands::daemon::DaemonArgs),
 }
 
 #[derive(Parser, Debug)]
@@ -62,6 +155,13 @@ pub enum ECommands {
 pub struct Cli {
     #[command(subcommand)]
     pub command: Option<ECommands>,
+    #[arg(
+        long = "format",
+        global = true,
+        value_enum,
+        help = "Machine-readable output for find, list, tmp list, sync and check"
+    )]
+    pub format: Option<commands::OutputFormat>,
 }
 
 trait ShellScriptName {
@@ -82,7 +182,30 @@ impl ShellScriptName for Shell {
 
 impl Cli {
     pub fn new() -> Self {
-        Cli::parse()
+        let cli = Cli::parse();
+        if cli.format.is_some() && !cli.supports_format() {
+            Cli::command()
+                .error(
+                    ErrorKind::ArgumentConflict,
+                    "--format is only supported by find, list, tmp list, sync and check",
+                )
+                .exit();
+        }
+        cli
+    }
+
+    /// Whether the command has a machine-readable output to select with `--format`.
+    fn supports_format(&self) -> bool {
+        match &self.command {
+            Some(
+                ECommands::Find { .. }
+                | ECommands::List { .. }
+                | ECommands::Sync
+                | ECommands::Check { .. },
+            ) => true,
+            Some(ECommands::Tmp(tmp)) => matches!(tmp.command, Some(TmpCommands::List)),
+            _ => false,
+        }
     }
     pub fn activate(shell: Shell) {
         let mut cmd = Cli::command();
@@ -191,3 +314,22 @@ impl Cli {
         None
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn supports_format(args: &[&str]) -> bool {
+        Cli::try_parse_from(args).unwrap().supports_format()
+    }
+
+    #[test]
+    fn test_format_only_on_commands_with_machine_readable_output() {
+        assert!(supports_format(&["prog", "--format", "json", "list"]));
+        assert!(supports_format(&["prog", "check", "--format", "tsv"]));
+        assert!(supports_format(&["prog", "tmp", "list", "--format", "ndjson"]));
+        assert!(!supports_format(&["prog", "tmp", "create", "--format", "json"]));
+        assert!(!supports_format(&["prog", "doctor", "--format", "json"]));
+        assert!(!supports_format(&["prog", "--format", "json", "tag", "add", "api", "work"]));
+    }
+}
@@ -1,14 +1,26 @@
-use std::path::Path;
-
+use crate::context::database::Database;
+use crate::context::database::models::RepoRemote;
 use crate::helpers::colors::Colorize;
+use crate::helpers::git;
 use crate::helpers::git::parse_git_url;
 use crate::helpers::git::remote_url_is_valid;
 use crate::{context::Context, helpers::platform};
 use log::debug;
 
-pub fn run(c: &mut Context, url: &str, rest: &[String]) {
-    let base_dir = c.get_base_dir().unwrap();
-    let url = c.config().replace_alias(url.to_owned());
+/// Where and how a repository gets cloned.
+#[derive(Debug, Clone)]
+pub struct CloneTarget {
+    pub url: String,
+    pub base_dir: String,
+    pub host: String,
+    pub owner: String,
+    pub name: String,
+    pub full_path: String,
+    pub clone_args: Vec<String>,
+}
+
+pub fn run(c: &mut Context, url: &str, base: Option<&str>, rest: &[String]) {
+    let url = clone_url(c.config().replace_alias(url.to_owned()));
 
     let url_parsed = match parse_git_url(&url) {
         Some(p) => p,
@@ -29,9 +41,17 @@ pub fn run(c: &mut Context, url: &str, rest: &[String]) {
     let name = url_parsed.name.clone();
     let fullname = url_parsed.fullname.clone();
 
+    let base_dir = match c.get_base_dir(base, &host, &owner, &name) {
+        Ok(base_dir) => base_dir,
+        Err(e) => {
+            eprintln!("{}", e.to_string().red());
+            return;
+        }
+    };
+
     debug!("host: {host}, full name: {fullname}, base dir: {base_dir}");
 
-    let full_path = Path::new(&base_dir).join(&host).join(&owner).join(&name);
+    let full_path = c.config().repo_path(&base_dir, &host, &owner, &name);
 
     if full_path.exists() {
         println!("{}", format!("Repo already exists: {}", full_path.display()).green());
@@ -44,24 +64,99 @@ pub fn run(c: &mut Context, url: &str, rest: &[String]) {
         full_path.to_str().unwrap_or_else(|| panic!("Cannot construct full path for {}", url));
     println!("{}", format!("Add: {}", url).green());
 
-    let result = crate::helpers::git::clone(&url, rest, target_path);
+    let target = CloneTarget {
+        url: url.clone(),
+        base_dir,
+        host,
+        owner,
+        name,
+        full_path: target_path.to_string(),
+        clone_args: rest.to_vec(),
+    };
 
-    if result.is_err() {
+    if clone_target(&target, false).is_err() {
         eprintln!("\n{}", format!("Failed to clone: {}", url).red());
         return;
     }
 
-    c.database_mut().record_item(&base_dir, &url, &host, &name, &owner, target_path);
-    c.database_mut().save().unwrap();
+    c.database_mut().update_with_history("add", |db| record_target(db, &target)).unwrap();
 
     println!("{}", format!("Cloned to: {}", target_path).green());
     platform::clipboard::copy_path(target_path);
 }
 
+/// The URL to clone for `url`. A forge page such as `.../tree/main` stands
+/// for the repository it belongs to.
+fn clone_url(url: String) -> String {
+    git::forge_clone_url(&url).unwrap_or(url)
+}
+
+/// Clones `target` with git. With `quiet`, git's output is only shown when
+/// the clone fails, so several clones can run side by side.
+pub fn clone_target(target: &CloneTarget, quiet: bool) -> anyhow::Result<()> {
+    if quiet {
+        git::clone_quiet(&target.url, &target.clone_args, &target.full_path)
+    } else {
+        git::clone(&target.url, &target.clone_args, &target.full_path)
+    }
+}
+
+/// Records a freshly cloned repository in the index.
+pub fn record_target(db: &mut Database, target: &CloneTarget) {
+    db.record_item(
+        &target.base_dir,
+        &target.url,
+        &target.host,
+        &target.name,
+        &target.owner,
+        &target.full_path,
+    );
+    db.update_record(&target.full_path, |record| {
+        record.clone_args = target.clone_args.clone();
+        record.remotes = vec![RepoRemote::new(clone_remote_name(&target.clone_args), &target.url)];
+    });
+}
+
+/// Name git gives the cloned remote, honouring `-o`/`--origin`.
+fn clone_remote_name(clone_args: &[String]) -> &str {
+    let mut args = clone_args.iter();
+    while let Some(arg) = args.next() {
+        if arg == "-o" || arg == "--origin" {
+            if let Some(name) = args.next() {
+                return name;
+            }
+        } else if let Some(name) = arg.strip_prefix("--origin=") {
+            return name;
+        }
+    }
+    "origin"
+}
+
 #[cfg(test)]
 mod tests {
+    use super::{clone_remote_name, clone_url};
+    use crate::helpers::git::parse_git_url;
     use std::path::Path;
 
+    #[test]
+    fn test_add_page_url_clones_its_repository() {
+        let url = clone_url("https://github.com/acme/api/tree/main".to_string());
+        assert_eq!(url, "https://github.com/acme/api.git");
+        let parsed = parse_git_url(&url).unwrap();
+        assert_eq!((parsed.owner.as_deref(), parsed.name.as_str()), (Some("acme"), "api"));
+
+        let url = "git@github.com:acme/api.git".to_string();
+        assert_eq!(clone_url(url.clone()), url);
+    }
+
+    #[test]
+    fn test_clone_remote_name() {
+        let args = |a: &[&str]| a.iter().map(|s| s.to_string()).collect::<Vec<_>>();
+        assert_eq!(clone_remote_name(&args(&[])), "origin");
+        assert_eq!(clone_remote_name(&args(&["--depth", "1", "-o", "upstream"])), "upstream");
+        assert_eq!(clone_remote_name(&args(&["--origin=fork"])), "fork");
+    }
+
     #[test]
     fn test_path_construction_uses_native_separators() {
         // This test verifies that the path construction uses native path separators
@@ -0,0 +1,264 @@
+use std::collections::HashSet;
+use std::path::Path;
+
+use super::add::{self, CloneTarget};
+use super::printer::group::print_group;
+use crate::context::Context;
+use crate::context::database::models::Repo;
+use crate::helpers::colors::Colorize;
+use crate::helpers::git::parse_git_url;
+use crate::internal::manifest::{Manifest, ManifestRepo};
+use rayon::prelude::*;
+
+/// A manifest entry resolved to a location on this machine.
+#[derive(Debug)]
+struct Planned {
+    target: CloneTarget,
+    tags: Vec<String>,
+    tracked: bool,
+}
+
+#[derive(Debug, Default)]
+struct Plan {
+    clone: Vec<Planned>,
+    present: Vec<Planned>,
+    invalid: Vec<(String, String)>,
+    extra: Vec<String>,
+}
+
+/// Splits a manifest path into host, owner and name. The path mirrors the
+/// clone layout, so it is preferred over re-parsing the remote.
+fn identify(entry: &ManifestRepo) -> Option<(String, String, String)> {
+    let segments: Vec<&str> = entry.path.split('/').collect();
+    if segments.len() >= 3 {
+        let host = segments[0].to_string();
+        let owner = segments[1..segments.len() - 1].join("/");
+        let name = segments[segments.len() - 1].to_string();
+        return Some((host, owner, name));
+    }
+
+    let parsed = parse_git_url(&entry.remote)?;
+    Some((parsed.host?, parsed.owner?, parsed.name))
+}
+
+/// Works out which manifest entries need cloning. `resolve_base` maps an
+/// entry (with its host, owner and name) to a base dir on this machine.
+fn plan<F>(manifest: &Manifest, tracked: &[Repo], mut resolve_base: F) -> Plan
+where
+    F: FnMut(&ManifestRepo, &str, &str, &str) -> anyhow::Result<String>,
+{
+    let tracked_paths: HashSet<&str> = tracked.iter().map(|r| r.full_path.as_str()).collect();
+    let mut plan = Plan::default();
+    let mut wanted = HashSet::new();
+
+    for entry in &manifest.repos {
+        let Some((host, owner, name)) = identify(entry) else {
+            plan.invalid.push((entry.path.clone(), "Cannot tell host, owner and name".into()));
+            continue;
+        };
+        let base_dir = match resolve_base(entry, &host, &owner, &name) {
+            Ok(base_dir) => base_dir,
+            Err(e) => {
+                plan.invalid.push((entry.path.clone(), e.to_string()));
+                continue;
+            }
+        };
+
+        let full_path =
+            entry.path.split('/').fold(Path::new(&base_dir).to_path_buf(), |p, s| p.join(s));
+        let full_path = full_path.to_string_lossy().to_string();
+        wanted.insert(full_path.clone());
+
+        let planned = Planned {
+            tracked: tracked_paths.contains(full_path.as_str()),
+            target: CloneTarget {
+                url: entry.remote.clone(),
+                base_dir,
+                host,
+                owner,
+                name,
+                full_path,
+                clone_args: entry.clone_args.clone(),
+            },
+            tags: entry.tags.clone(),
+        };
+
+        if Path::new(&planned.target.full_path).exists() {
+            plan.present.push(planned);
+        } else {
+            plan.clone.push(planned);
+        }
+    }
+
+    plan.extra = tracked
+        .iter()
+        .filter(|r| !wanted.contains(&r.full_path))
+        .map(|r| r.full_path.clone())
+        .collect();
+    plan
+}
+
+/// Clones every planned repository in parallel, returning the failures.
+fn clone_all(planned: &[Planned]) -> Vec<(String, String)> {
+    planned
+        .par_iter()
+        .filter_map(|p| {
+            add::clone_target(&p.target, true)
+                .err()
+                .map(|e| (p.target.full_path.clone(), e.to_string().replace('\n', " ")))
+        })
+        .collect()
+}
+
+/// Applies a workspace manifest: clones what is missing and records it with
+/// its tags. Returns the number of entries that could not be applied.
+pub fn run(c: &mut Context, path: &Path, dry_run: bool) -> usize {
+    let contents = match std::fs::read_to_string(path) {
+        Ok(contents) => contents,
+        Err(e) => {
+            eprintln!("{}", format!("Unable to read {}: {}", path.display(), e).red());
+            return 1;
+        }
+    };
+    let manifest = match Manifest::parse(&contents) {
+        Ok(manifest) => manifest,
+        Err(e) => {
+            eprintln!("{}", e.red());
+            return 1;
+        }
+    };
+
+    c.sync_silent();
+    let tracked = c.database().get_all_items();
+    let plan = plan(&manifest, &tracked, |entry, host, owner, name| {
+        c.get_base_dir(Some(&entry.base), host, owner, name)
+            .or_else(|_| c.get_base_dir(None, host, owner, name))
+    });
+
+    let paths = |planned: &[Planned]| -> Vec<(String, String)> {
+        planned.iter().map(|p| (p.target.full_path.clone(), p.target.url.clone())).collect()
+    };
+
+    if dry_run {
+        print_group(&"+ Would clone".to_string().green().to_string(), paths(&plan.clone));
+        print_group(&"✓ Already present".to_string().green().to_string(), paths(&plan.present));
+        print_group(&"✗ Invalid".to_string().red().to_string(), plan.invalid.clone());
+        print_group(&"? Not in manifest".to_string().yellow().to_string(), extra(&plan));
+        return plan.invalid.len();
+    }
+
+    if !plan.clone.is_empty() {
+        println!("Cloning {} repositories...", plan.clone.len());
+    }
+    let failed = clone_all(&plan.clone);
+    let failed_paths: HashSet<&str> = failed.iter().map(|(p, _)| p.as_str()).collect();
+    let cloned: Vec<&Planned> =
+        plan.clone.iter().filter(|p| !failed_paths.contains(p.target.full_path.as_str())).collect();
+
+    let result = c.database_mut().update_with_history("apply", |db| {
+        for planned in cloned.iter().copied().chain(plan.present.iter()) {
+            if !planned.tracked {
+                add::record_target(db, &planned.target);
+            }
+            db.add_tags(&planned.target.full_path, &planned.tags);
+        }
+    });
+    if let Err(e) = result {
+        eprintln!("{}", e.red());
+    }
+
+    let cloned = cloned.into_iter().map(|p| (p.target.full_path.clone(), String::new())).collect();
+    print_group(&"+ Cloned".to_string().green().to_string(), cloned);
+    print_group(&"✗ Failed".to_string().red().to_string(), failed.clone());
+    print_group(&"✗ Invalid".to_string().red().to_string(), plan.invalid.clone());
+    print_group(&"? Not in manifest".to_string().yellow().to_string(), extra(&plan));
+    println!("{}", format!("{} already present", plan.present.len()).green());
+
+    failed.len() + plan.invalid.len()
+}
+
+fn extra(plan: &Plan) -> Vec<(String, String)> {
+    plan.extra.iter().map(|p| (p.clone(), String::new())).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use std::process::Command;
+    use tempfile::TempDir;
+
+    fn git(dir: &Path, args: &[&str]) {
+        let status = Command::new("git").args(args).current_dir(dir).output().unwrap().status;
+        assert!(status.success(), "git {:?} failed", args);
+    }
+
+    fn entry(remote: &str, path: &str) -> ManifestRepo {
+        ManifestRepo {
+            remote: remote.to_string(),
+            path: path.to_string(),
+            base: "~/work".to_string(),
+            tags: vec!["work".to_string()],
+            clone_args: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_plan_splits_missing_present_and_extra() {
+        let temp_dir = TempDir::new().unwrap();
+        let base = temp_dir.path().to_str().unwrap().to_string();
+        fs::create_dir_all(temp_dir.path().join("github.com/user/present")).unwrap();
+
+        let manifest = Manifest {
+            version: 1,
+            repos: vec![
+                entry("https://github.com/user/present.git", "github.com/user/present"),
+                entry("https://github.com/user/missing.git", "github.com/user/missing"),
+                entry("not a url", "oops"),
+            ],
+        };
+        let tracked =
+            vec![Repo { full_path: format!("{}/github.com/user/old", base), ..Default::default() }];
+
+        let plan = plan(&manifest, &tracked, |_, _, _, _| Ok(base.clone()));
+
+        assert_eq!(plan.present.len(), 1);
+        assert_eq!(plan.clone.len(), 1);
+        assert_eq!(plan.clone[0].target.owner, "user");
+        assert_eq!(plan.clone[0].target.name, "missing");
+        assert_eq!(plan.clone[0].target.full_path, format!("{}/github.com/user/missing", base));
+        assert_eq!(plan.invalid.len(), 1);
+        assert_eq!(plan.extra, vec![format!("{}/github.com/user/old", base)]);
+    }
+
+    #[test]
+    fn test_clone_all_clones_file_remotes() {
+        let temp_dir = TempDir::new().unwrap();
+        let source = temp_dir.path().join("remotes/user/source");
+        fs::create_dir_all(&source).unwrap();
+        git(&source, &["init"]);
+        git(&source, &["config", "user.email", "test@example.com"]);
+        git(&source, &["config", "user.name", "Test User"]);
+        fs::write(source.join("README.md"), "hello").unwrap();
+        git(&source, &["add", "."]);
+        git(&source, &["commit", "-m", "init"]);
+
+        let base = temp_dir.path().join("base");
+        let base = base.to_str().unwrap().to_string();
+        let remote = format!("file://{}", source.display());
+        let manifest = Manifest {
+            version: 1,
+            repos: vec![
+                entry(&remote, "localhost/user/source"),
+                entry("file:///nonexistent/user/gone", "localhost/user/gone"),
+            ],
+        };
+
+        let plan = plan(&manifest, &[], |_, _, _, _| Ok(base.clone()));
+        let failed = clone_all(&plan.clone);
+
+        assert_eq!(failed.len(), 1);
+        assert!(failed[0].0.ends_with("gone"));
+        assert!(Path::new(&base).join("localhost/user/source/README.md").exists());
+    }
+}
@@ -1,7 +1,12 @@
+use super::printer::format::{OutputFormat, Tabular, print_rows};
+use super::printer::group::print_group;
 use crate::context::core::Context;
+use crate::context::database::Query;
 use crate::context::database::models::Repo;
 use crate::helpers::colors::Colorize;
-use crate::helpers::git::{RepoStatus, get_repo_status};
+use crate::helpers::git::{
+    RepoStatus, Submodule, get_repo_status, get_submodules, unpushed_commits,
+};
 use rayon::prelude::*;
 use serde::Serialize;
 
@@ -9,65 +14,139 @@ use serde::Serialize;
 struct CheckResult {
     repo: Repo,
     status: Option<RepoStatus>,
+    /// Path of the superproject when this is a submodule.
+    submodule_of: Option<String>,
 }
 
+/// Status of a repository in `check --format` output.
 #[derive(Serialize)]
-struct JsonStatus<'a> {
+struct CheckStatus<'a> {
     #[serde(flatten)]
     inner: &'a RepoStatus,
     dirty: bool,
     unpushed: bool,
     no_upstream: bool,
+    never_pushed: bool,
 }
 
+/// A repository in `check --format` output.
 #[derive(Serialize)]
-struct JsonEntry<'a> {
+struct CheckEntry<'a> {
     path: &'a str,
     remote_url: &'a str,
-    status: Option<JsonStatus<'a>>,
+    tags: &'a [String],
+    #[serde(skip_serializing_if = "Option::is_none")]
+    submodule_of: Option<&'a str>,
+    status: Option<CheckStatus<'a>>,
 }
 
-fn collect_results(c: &mut Context) -> Vec<CheckResult> {
+impl Tabular for CheckEntry<'_> {
+    const COLUMNS: &'static [&'static str] = &[
+        "path",
+        "remote_url",
+        "tags",
+        "submodule_of",
+        "branch",
+        "upstream",
+        "ahead",
+        "behind",
+        "modified",
+        "untracked",
+        "conflicted",
+        "detached",
+        "dirty",
+        "unpushed",
+        "no_upstream",
+        "never_pushed",
+    ];
+
+    fn cells(&self) -> Vec<String> {
+        let mut cells = vec![
+            self.path.to_string(),
+            self.remote_url.to_string(),
+            self.tags.join(","),
+            self.submodule_of.unwrap_or_default().to_string(),
+        ];
+        // An unreadable repository has no status, so its columns stay empty.
+        match &self.status {
+            Some(status) => cells.extend([
+                status.inner.branch.clone(),
+                status.inner.upstream.clone().unwrap_or_default(),
+                status.inner.ahead.to_string(),
+                status.inner.behind.to_string(),
+                status.inner.modified.to_string(),
+                status.inner.untracked.to_string(),
+                status.inner.conflicted.to_string(),
+                status.inner.detached.to_string(),
+                status.dirty.to_string(),
+                status.unpushed.to_string(),
+                status.no_upstream.to_string(),
+                status.never_pushed.to_string(),
+            ]),
+            None => cells.resize(Self::COLUMNS.len(), String::new()),
+        }
+        cells
+    }
+}
+
+fn collect_results(c: &mut Context, tags: &[String], query: &str) -> Vec<CheckResult> {
     c.sync_silent();
-    let items = c.database_mut().get_all_items();
+    let query = Query::parse(query);
+    let mut items = c.database_mut().get_all_items();
+    items.retain(|item| {
+        tags.iter().all(|tag| item.has_tag(tag)) && query.matches(item) && !c.is_excluded(item)
+    });
     items
         .into_par_iter()
-        .map(|repo| {
+        .flat_map(|repo| {
+            let submodules = submodule_results(&repo.full_path, &repo.submodules);
             let status = get_repo_status(&repo.full_path);
-            CheckResult { repo, status }
+            let mut results = vec![CheckResult { repo, status, submodule_of: None }];
+            results.extend(submodules);
+            results
         })
         .collect()
 }
 
-fn print_group(title: &str, mut entries: Vec<(String, String)>) {
-    if entries.is_empty() {
-        return;
-    }
-    entries.sort_by(|a, b| a.0.cmp(&b.0));
-    println!("{} ({})", title, entries.len());
-    let max_path = entries.iter().map(|(p, _)| p.len()).max().unwrap_or(0);
-    for (path, detail) in entries {
-        if detail.is_empty() {
-            println!("  {}", path);
-        } else {
-            println!("  {:width$}  {}", path, detail, width = max_path);
-        }
+/// Checks the checked out `submodules` of `superproject`, and theirs in turn.
+/// A submodule usually sits on a detached commit, so what counts as unpushed
+/// is the commits that are on no remote at all.
+fn submodule_results(superproject: &str, submodules: &[Submodule]) -> Vec<CheckResult> {
+    let mut results = Vec::new();
+    for submodule in submodules.iter().filter(|s| s.is_initialized(superproject)) {
+        let path = submodule.full_path(superproject);
+        let status = get_repo_status(&path)
+            .map(|status| RepoStatus { ahead: unpushed_commits(&path).unwrap_or(0), ..status });
+        let repo = Repo {
+            full_path: path.clone(),
+            remote_url: submodule.url.clone(),
+            ..Default::default()
+        };
+        results.push(CheckResult { repo, status, submodule_of: Some(superproject.to_string()) });
+        results.extend(submodule_results(&path, &get_submodules(&path)));
     }
-    println!();
+    results
 }
 
 /// Run the check command. Returns the number of repositories with issues
 /// (dirty, unpushed, no-upstream, detached, or unreadable).
-pub fn run(c: &mut Context, dirty_only: bool, json: bool) -> usize {
-    let results = collect_results(c);
+pub fn run(
+    c: &mut Context,
+    dirty_only: bool,
+    format: Option<OutputFormat>,
+    tags: &[String],
+    query: &str,
+) -> usize {
+    let results = collect_results(c, tags, query);
 
-    if json {
-        return print_json(&results, dirty_only);
+    if let Some(format) = format {
+        return print_entries(&results, dirty_only, format);
     }
 
     let mut dirty: Vec<(String, String)> = Vec::new();
     let mut unpushed: Vec<(String, String)> = Vec::new();
     let mut no_upstream: Vec<(String, String)> = Vec::new();
+    let mut never_pushed: Vec<(String, String)> = Vec::new();
     let mut detached: Vec<(String, String)> = Vec::new();
     let mut errored: Vec<(String, String)> = Vec::new();
 
@@ -99,28 +178,45 @@ pub fn run(c: &mut Context, dirty_only: bool, json: bool) -> usize {
             continue;
         }
 
-        if status.is_unpushed() {
-            let detail = match &status.upstream {
-                Some(up) => format!("ahead {} [{} -> {}]", status.ahead, status.branch, up),
-                None => format!("ahead {} [{}]", status.ahead, status.branch),
-            };
-            unpushed.push((path.clone(), detail));
+        // Without a remote there is nothing to push to, so upstream and ahead
+        // counts say nothing.
+        if r.submodule_of.is_some() {
+            if status.is_unpushed() {
+                unpushed.push((path.clone(), format!("{} commits on no remote", status.ahead)));
+            }
+            continue;
         }
-        if status.is_no_upstream() {
-            no_upstream.push((path.clone(), format!("branch: {}", status.branch)));
+        if r.repo.is_local() {
+            never_pushed.push((path.clone(), String::from("no remote")));
+        } else {
+            if status.is_unpushed() {
+                let detail = match &status.upstream {
+                    Some(up) => format!("ahead {} [{} -> {}]", status.ahead, status.branch, up),
+                    None => format!("ahead {} [{}]", status.ahead, status.branch),
+                };
+                unpushed.push((path.clone(), detail));
+            }
+            if status.is_no_upstream() {
+                no_upstream.push((path.clone(), format!("branch: {}", status.branch)));
+            }
         }
         if status.detached {
             detached.push((path.clone(), String::from("detached HEAD")));
         }
     }
 
-    let issue_count =
-        dirty.len() + unpushed.len() + no_upstream.len() + detached.len() + errored.len();
+    let issue_count = dirty.len()
+        + unpushed.len()
+        + no_upstream.len()
+        + never_pushed.len()
+        + detached.len()
+        + errored.len();
 
     print_group(&"⚠ Dirty".to_string().red().to_string(), dirty);
     if !dirty_only {
         print_group(&"⬆ Unpushed".to_string().yellow().to_string(), unpushed);
         print_group(&"⚑ No upstream".to_string().yellow().to_string(), no_upstream);
+        print_group(&"○ Never pushed".to_string().yellow().to_string(), never_pushed);
         print_group(&"⎇ Detached HEAD".to_string().yellow().to_string(), detached);
     }
     print_group(&"✗ Unreadable".to_string().red().to_string(), errored);
@@ -135,27 +231,24 @@ pub fn run(c: &mut Context, dirty_only: bool, json: bool) -> usize {
     issue_count
 }
 
-fn print_json(results: &[CheckResult], dirty_only: bool) -> usize {
+fn print_entries(results: &[CheckResult], dirty_only: bool, format: OutputFormat) -> usize {
     let mut issue_count = 0;
-    let mut entries: Vec<JsonEntry> = Vec::new();
+    let mut entries: Vec<CheckEntry> = Vec::new();
     for r in results {
         let (status_obj, has_issue) = match &r.status {
             Some(s) => {
                 let dirty = s.is_dirty();
+                let is_submodule = r.submodule_of.is_some();
+                let never_pushed = !is_submodule && r.repo.is_local();
+                let unpushed = !never_pushed && s.is_unpushed();
+                let no_upstream = !never_pushed && !is_submodule && s.is_no_upstream();
+                let detached = !is_submodule && s.detached;
                 let issue = if dirty_only {
                     dirty
                 } else {
-                    dirty || s.is_unpushed() || s.is_no_upstream() || s.detached
+                    dirty || unpushed || no_upstream || never_pushed || detached
                 };
-                (
-                    Some(JsonStatus {
-                        inner: s,
-                        dirty,
-                        unpushed: s.is_unpushed(),
-                        no_upstream: s.is_no_upstream(),
-                    }),
-                    issue,
-                )
+                (Some(CheckStatus { inner: s, dirty, unpushed, no_upstream, never_pushed }), issue)
             }
             None => (None, true),
         };
@@ -167,15 +260,14 @@ fn print_json(results: &[CheckResult], dirty_only: bool) -> usize {
             issue_count += 1;
         }
 
-        entries.push(JsonEntry {
+        entries.push(CheckEntry {
             path: &r.repo.full_path,
             remote_url: &r.repo.remote_url,
+            tags: &r.repo.tags,
+            submodule_of: r.submodule_of.as_deref(),
             status: status_obj,
         });
     }
-    match serde_json::to_string(&entries) {
-        Ok(s) => println!("{}", s),
-        Err(e) => eprintln!("Failed to serialize JSON: {}", e),
-    }
+    print_rows(format, &entries);
     issue_count
 }
@@ -28,9 +28,9 @@ pub fn run(c: &Context, skip_confirmation: bool) {
         }
     }
 
-    c.database_mut().reset();
-    println!("Successfully clean the database.");
-    if let Err(e) = c.database_mut().save() {
+    if let Err(e) = c.database_mut().update_with_history("clean", |db| db.reset()) {
         error!("Failed to save database: {}", e);
+        return;
     }
+    println!("Successfully clean the database.");
 }
@@ -0,0 +1,68 @@
+use crate::context::Context;
+use crate::helpers::colors::Colorize;
+use crate::internal::daemon::{self, Request, Response};
+use clap::{Args, Subcommand};
+
+#[derive(Debug, Args)]
+#[command(args_conflicts_with_subcommands = true)]
+pub struct DaemonArgs {
+    #[command(subcommand)]
+    pub command: Option<DaemonCommands>,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum DaemonCommands {
+    #[command(about = "Watch the base dirs in the foreground (the default)")]
+    Run,
+    #[command(about = "Show whether the daemon is running")]
+    Status,
+    #[command(about = "Stop the running daemon")]
+    Stop,
+}
+
+/// Runs a daemon command. Returns the number of problems (non-zero when the
+/// daemon is not running or failed).
+pub fn run(c: &Context, command: Option<&DaemonCommands>) -> usize {
+    match command {
+        None | Some(DaemonCommands::Run) => serve(c),
+        Some(DaemonCommands::Status) => status(),
+        Some(DaemonCommands::Stop) => stop(),
+    }
+}
+
+fn serve(c: &Context) -> usize {
+    match daemon::serve(c) {
+        Ok(()) => 0,
+        Err(e) => {
+            eprintln!("{}", e.to_string().red());
+            1
+        }
+    }
+}
+
+fn status() -> usize {
+    match daemon::request(&Request::Status) {
+        Some(Response::Status { pid, watches, pending }) => {
+            println!("{}", format!("Running with pid {}", pid).green());
+            println!("Watching {} directories, {} waiting to be scanned", watches, pending);
+            0
+        }
+        _ => {
+            println!("{}", "Not running.".yellow());
+            1
+        }
+    }
+}
+
+fn stop() -> usize {
+    match daemon::request(&Request::Stop) {
+        Some(Response::Stopping) => {
+            println!("{}", "Stopped.".green());
+            0
+        }
+        _ => {
+            eprintln!("{}", "The daemon is not running.".red());
+            1
+        }
+    }
+}
@@ -0,0 +1,123 @@
+use crate::context::database::{CURRENT_VERSION, Database};
+use crate::helpers::colors::Colorize;
+use clap::{Args, Subcommand};
+
+#[derive(Debug, Args)]
+#[command(args_conflicts_with_subcommands = true)]
+pub struct DbArgs {
+    #[command(subcommand)]
+    pub command: Option<DbCommands>,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum DbCommands {
+    #[command(about = "Upgrade the index file to the current schema")]
+    Migrate {
+        #[arg(long = "check", help = "Only report pending migrations, exit 1 if there are any")]
+        check: bool,
+    },
+    #[command(about = "List the saved snapshots of the index")]
+    History,
+    #[command(about = "Roll the index back to a snapshot")]
+    Restore {
+        #[arg(help = "Snapshot id from `db history`, or a unique prefix of it")]
+        id: String,
+    },
+}
+
+/// Run a database maintenance command. Returns the number of pending issues
+/// (non-zero for `migrate --check` with pending migrations, or on failure).
+pub fn run(db: &DbCommands) -> usize {
+    match db {
+        DbCommands::Migrate { check } => migrate(*check),
+        DbCommands::History => history(),
+        DbCommands::Restore { id } => restore(id),
+    }
+}
+
+fn history() -> usize {
+    let snapshots = Database::history();
+    if snapshots.is_empty() {
+        println!("No snapshots yet.");
+        return 0;
+    }
+
+    let max_command = snapshots.iter().map(|s| s.command.len()).max().unwrap_or(0);
+    for snapshot in snapshots {
+        let created_at = snapshot
+            .created_at
+            .map(|t| t.format("%Y-%m-%d %H:%M:%S").to_string())
+            .unwrap_or_default();
+        println!(
+            "{}  {}  before {:width$}  {} repositories",
+            snapshot.id.blue(),
+            created_at,
+            snapshot.command,
+            snapshot.records,
+            width = max_command
+        );
+    }
+    0
+}
+
+fn restore(id: &str) -> usize {
+    match Database::restore(id) {
+        Ok(snapshot) => {
+            println!(
+                "{}",
+                format!(
+                    "Restored {} repositories from snapshot {} (taken before `{}`).",
+                    snapshot.records, snapshot.id, snapshot.command
+                )
+                .green()
+            );
+            0
+        }
+        Err(e) => {
+            eprintln!("{}", e.red());
+            1
+        }
+    }
+}
+
+fn migrate(check: bool) -> usize {
+    let (version, pending) = match Database::pending_migrations() {
+        Ok(result) => result,
+        Err(e) => {
+            eprintln!("{}", e.red());
+            return 1;
+        }
+    };
+
+    if version > CURRENT_VERSION {
+        eprintln!(
+            "{}",
+            format!(
+                "Database schema v{} is newer than this version of prog supports (v{}).",
+                version, CURRENT_VERSION
+            )
+            .red()
+        );
+        return 1;
+    }
+
+    if pending.is_empty() {
+        println!("{}", format!("Database is up to date (schema v{}).", version).green());
+        return 0;
+    }
+
+    println!("Database schema v{}, current is v{}.", version, CURRENT_VERSION);
+    println!("Pending migrations:");
+    for migration in &pending {
+        println!("  v{} -> v{}: {}", migration.from, migration.from + 1, migration.description);
+    }
+
+    if check {
+        return pending.len();
+    }
+
+    // Opening the database runs the migrations and saves the result.
+    Database::new();
+    println!("{}", format!("Migrated to schema v{}.", CURRENT_VERSION).green());
+    0
+}
@@ -0,0 +1,342 @@
+use super::printer::group::print_group;
+use crate::context::Context;
+use crate::context::database::models::Repo;
+use crate::helpers::colors::Colorize;
+use crate::helpers::git::{has_worktrees, parse_git_url, remote_url_is_valid, repair_worktrees};
+use crate::helpers::path::{exists, is_dir_effectively_empty, remove_dir_with_empty_parents};
+use anyhow::{Context as _, bail};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// What is wrong with a record of the index.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Issue {
+    /// The directory is gone.
+    Missing,
+    /// The remote gives no host, owner and name to lay the repository out by.
+    UnparsableRemote,
+    /// The repository is not where `add` would clone its remote.
+    Misplaced { expected: PathBuf },
+    /// Another record is the same directory under a different path.
+    SameDirectory { other: String },
+    /// Another record is a clone of the same remote.
+    SameRemote { other: String },
+}
+
+#[derive(Debug)]
+struct Finding {
+    repo: Repo,
+    issue: Issue,
+}
+
+/// Looks for records that drifted from the layout. `expected_path` gives the
+/// path `add` computes for a base dir, host, owner and name. Worktrees and
+/// repositories without a remote have no place in the layout and are only
+/// checked for being on disk.
+fn diagnose<F>(repos: &[Repo], expected_path: F) -> Vec<Finding>
+where
+    F: Fn(&str, &str, &str, &str) -> PathBuf,
+{
+    let mut findings = Vec::new();
+    let mut directories: HashMap<PathBuf, &Repo> = HashMap::new();
+    let mut remotes: HashMap<String, &Repo> = HashMap::new();
+
+    for repo in repos {
+        if !exists(&repo.full_path) {
+            findings.push(Finding { repo: repo.clone(), issue: Issue::Missing });
+            continue;
+        }
+        let directory =
+            fs::canonicalize(&repo.full_path).unwrap_or_else(|_| PathBuf::from(&repo.full_path));
+        if let Some(other) = directories.get(&directory) {
+            let other = other.full_path.clone();
+            findings.push(Finding { repo: repo.clone(), issue: Issue::SameDirectory { other } });
+            continue;
+        }
+        directories.insert(directory, repo);
+
+        if repo.is_local() || repo.worktree_of.is_some() {
+            continue;
+        }
+        let parsed = match parse_git_url(&repo.remote_url) {
+            Some(parsed) if remote_url_is_valid(&parsed) => parsed,
+            _ => {
+                findings.push(Finding { repo: repo.clone(), issue: Issue::UnparsableRemote });
+                continue;
+            }
+        };
+        // The same remote over ssh and https, with or without `.git`.
+        let remote = format!("{}/{}", parsed.host.as_deref().unwrap_or_default(), parsed.fullname)
+            .to_lowercase();
+        if let Some(other) = remotes.get(&remote) {
+            let other = other.full_path.clone();
+            findings.push(Finding { repo: repo.clone(), issue: Issue::SameRemote { other } });
+        } else {
+            remotes.insert(remote, repo);
+        }
+
+        let expected = expected_path(
+            &repo.base_dir,
+            parsed.host.as_deref().unwrap_or_default(),
+            parsed.owner.as_deref().unwrap_or_default(),
+            &parsed.name,
+        );
+        if expected != Path::new(&repo.full_path) {
+            findings.push(Finding { repo: repo.clone(), issue: Issue::Misplaced { expected } });
+        }
+    }
+    findings
+}
+
+/// Run the doctor command. Reports records that drifted from the layout and,
+/// with `fix`, repairs what can be repaired. Returns the number of issues
+/// left.
+pub fn run(c: &mut Context, fix: bool) -> usize {
+    c.sync_silent();
+    let mut repos = c.database().get_all_items();
+    repos.retain(|repo| !c.is_excluded(repo));
+    let findings = diagnose(&repos, |base_dir, host, owner, name| {
+        c.config().repo_path(base_dir, host, owner, name)
+    });
+
+    if fix {
+        return apply_fixes(c, &findings);
+    }
+
+    let mut missing: Vec<(String, String)> = Vec::new();
+    let mut unparsable: Vec<(String, String)> = Vec::new();
+    let mut misplaced: Vec<(String, String)> = Vec::new();
+    let mut duplicates: Vec<(String, String)> = Vec::new();
+    for finding in &findings {
+        let path = finding.repo.full_path.clone();
+        match &finding.issue {
+            Issue::Missing => missing.push((path, String::from("not on disk"))),
+            Issue::UnparsableRemote => unparsable.push((path, finding.repo.remote_url.clone())),
+            Issue::Misplaced { expected } => {
+                misplaced.push((path, format!("-> {}", expected.display())))
+            }
+            Issue::SameDirectory { other } => {
+                duplicates.push((path, format!("same directory as {}", other)))
+            }
+            Issue::SameRemote { other } => {
+                duplicates.push((path, format!("same remote as {}", other)))
+            }
+        }
+    }
+
+    print_group(&"↪ Misplaced".to_string().yellow().to_string(), misplaced);
+    print_group(&"✗ Missing".to_string().red().to_string(), missing);
+    print_group(&"? Unparsable remote".to_string().red().to_string(), unparsable);
+    print_group(&"⧉ Duplicate".to_string().yellow().to_string(), duplicates);
+
+    if findings.is_empty() {
+        println!("{}", format!("All {} repositories are in place.", repos.len()).green());
+    } else {
+        println!(
+            "{}",
+            format!("{} issues found, run `prog doctor --fix` to repair them.", findings.len())
+                .red()
+        );
+    }
+    findings.len()
+}
+
+/// Drops missing and duplicate directory records, and moves misplaced
+/// repositories where `add` would have put them. Unparsable remotes and
+/// several clones of one remote need a decision and are only reported.
+/// Returns the number of issues left.
+fn apply_fixes(c: &Context, findings: &[Finding]) -> usize {
+    let mut forgotten: Vec<String> = Vec::new();
+    let mut moved: Vec<(Repo, PathBuf)> = Vec::new();
+    let mut left = 0;
+
+    for finding in findings {
+        let path = &finding.repo.full_path;
+        match &finding.issue {
+            Issue::Missing | Issue::SameDirectory { .. } => {
+                println!("{}", format!("Forget: {}", path).green());
+                forgotten.push(path.clone());
+            }
+            Issue::Misplaced { expected } => match move_repo(&finding.repo, expected) {
+                Ok(()) => {
+                    println!("{}", format!("Move: {} -> {}", path, expected.display()).green());
+                    moved.push((finding.repo.clone(), expected.clone()));
+                }
+                Err(e) => {
+                    eprintln!("{}", format!("Cannot move {}: {:#}", path, e).red());
+                    left += 1;
+                }
+            },
+            Issue::UnparsableRemote => {
+                eprintln!(
+                    "{}",
+                    format!(
+                        "Unparsable remote, fix it by hand: {} ({})",
+                        path, finding.repo.remote_url
+                    )
+                    .yellow()
+                );
+                left += 1;
+            }
+            Issue::SameRemote { other } => {
+                eprintln!(
+                    "{}",
+                    format!("Same remote as {}, remove one by hand: {}", other, path).yellow()
+                );
+                left += 1;
+            }
+        }
+    }
+
+    if forgotten.is_empty() && moved.is_empty() {
+        if findings.is_empty() {
+            println!("{}", "Nothing to fix.".green());
+        }
+        return left;
+    }
+
+    let result = c.database_mut().update_with_history("doctor", |db| {
+        for path in &forgotten {
+            db.remove(path);
+        }
+        for (repo, to) in &moved {
+            let to = to.to_string_lossy();
+            db.move_record(&repo.full_path, &to, &repo.base_dir);
+            if let Some(parsed) = parse_git_url(&repo.remote_url) {
+                db.update_record(&to, |record| {
+                    record.host = parsed.host.clone().unwrap_or_default();
+                    record.owner = parsed.owner.clone().unwrap_or_default();
+                    record.repo = parsed.name.clone();
+                });
+            }
+            let worktrees: Vec<String> = db
+                .get_all_items()
+                .into_iter()
+                .filter(|item| item.worktree_of.as_deref() == Some(repo.full_path.as_str()))
+                .map(|item| item.full_path)
+                .collect();
+            for worktree in worktrees {
+                db.update_record(&worktree, |record| record.worktree_of = Some(to.to_string()));
+            }
+        }
+    });
+    if let Err(e) = result {
+        // The moves happened on disk; `prog sync` picks them up from there.
+        eprintln!(
+            "{}",
+            format!("Failed to save database: {}. Run `prog sync` to record the moves.", e).red()
+        );
+        left += forgotten.len() + moved.len();
+    }
+
+    if left == 0 {
+        println!("{}", "Done!".green());
+    }
+    left
+}
+
+/// Moves the working tree of `repo` to `to`, then removes the parent
+/// directories the move left empty below its base dir.
+fn move_repo(repo: &Repo, to: &Path) -> anyhow::Result<()> {
+    if to.exists() {
+        bail!("{} already exists", to.display());
+    }
+    if let Some(parent) = to.parent() {
+        fs::create_dir_all(parent)
+            .with_context(|| format!("Error when creating directory {}", parent.display()))?;
+    }
+    let from = Path::new(&repo.full_path);
+    fs::rename(from, to)
+        .with_context(|| format!("Error when moving {} to {}", from.display(), to.display()))?;
+
+    let base_dir = PathBuf::from(&repo.base_dir);
+    if let Some(parent) = from.parent()
+        && parent != base_dir
+        && parent.starts_with(&base_dir)
+        && is_dir_effectively_empty(parent)
+    {
+        remove_dir_with_empty_parents(&parent.to_path_buf(), Some(&base_dir))
+            .with_context(|| format!("Error when removing directory {}", parent.display()))?;
+    }
+
+    // Linked worktrees find their main working tree by path.
+    let to = to.to_string_lossy();
+    if has_worktrees(&to) {
+        repair_worktrees(&to)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn record(base: &Path, path: &str, remote_url: &str) -> Repo {
+        Repo {
+            full_path: base.join(path).to_string_lossy().to_string(),
+            base_dir: base.to_string_lossy().to_string(),
+            remote_url: remote_url.to_string(),
+            host: String::from("github.com"),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_diagnose_finds_drifted_records() {
+        let temp_dir = TempDir::new().unwrap();
+        let base = temp_dir.path();
+        for dir in ["github.com/acme/api", "api-renamed", "weird", "clone", "ssh-clone"] {
+            fs::create_dir_all(base.join(dir)).unwrap();
+        }
+
+        let repos = vec![
+            record(base, "github.com/acme/api", "https://github.com/acme/api.git"),
+            record(base, "github.com/acme/api/", "https://github.com/acme/api.git"),
+            record(base, "api-renamed", "git@github.com:acme/web.git"),
+            record(base, "github.com/acme/gone", "https://github.com/acme/gone.git"),
+            record(base, "weird", "not a url"),
+            record(base, "clone", "https://github.com/acme/api.git"),
+            record(base, "ssh-clone", "git@github.com:acme/api"),
+        ];
+        let findings = diagnose(&repos, |base_dir, host, owner, name| {
+            Path::new(base_dir).join(host).join(owner).join(name)
+        });
+        let issues: Vec<(&str, &Issue)> = findings
+            .iter()
+            .map(|f| (f.repo.full_path.strip_prefix(repos[0].base_dir.as_str()).unwrap(), &f.issue))
+            .collect();
+
+        let api = repos[0].full_path.clone();
+        assert_eq!(
+            issues,
+            vec![
+                ("/github.com/acme/api/", &Issue::SameDirectory { other: api.clone() }),
+                ("/api-renamed", &Issue::Misplaced { expected: base.join("github.com/acme/web") }),
+                ("/github.com/acme/gone", &Issue::Missing),
+                ("/weird", &Issue::UnparsableRemote),
+                ("/clone", &Issue::SameRemote { other: api.clone() }),
+                ("/clone", &Issue::Misplaced { expected: base.join("github.com/acme/api") }),
+                ("/ssh-clone", &Issue::SameRemote { other: api }),
+                ("/ssh-clone", &Issue::Misplaced { expected: base.join("github.com/acme/api") }),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_move_repo_cleans_up_empty_parents() {
+        let temp_dir = TempDir::new().unwrap();
+        let base = temp_dir.path();
+        fs::create_dir_all(base.join("github.com/old-owner/api")).unwrap();
+        fs::create_dir_all(base.join("github.com/acme")).unwrap();
+
+        let repo = record(base, "github.com/old-owner/api", "https://github.com/acme/api.git");
+        move_repo(&repo, &base.join("github.com/acme/api")).unwrap();
+
+        assert!(base.join("github.com/acme/api").is_dir());
+        assert!(!base.join("github.com/old-owner").exists());
+        assert!(base.join("github.com").is_dir());
+        assert!(move_repo(&repo, &base.join("github.com/acme/api")).is_err());
+    }
+}
@@ -0,0 +1,14 @@
+use crate::context::Context;
+use crate::helpers::colors::Colorize;
+use crate::internal::manifest::Manifest;
+
+pub fn run(c: &Context) {
+    c.auto_sync_silent();
+    let mut repos = c.database().get_all_items();
+    repos.sort_by(|a, b| a.full_path.cmp(&b.full_path));
+
+    match Manifest::from_repos(&repos).to_toml() {
+        Ok(manifest) => print!("{}", manifest),
+        Err(e) => eprintln!("{}", e.red()),
+    }
+}
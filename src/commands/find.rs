@@ -1,25 +1,39 @@
 use crate::{
     context::{
         Context,
-        database::{MatchKind, MatchedRepo},
+        database::{MatchKind, MatchedRepo, Query, models::Repo},
+    },
+    helpers::{
+        colors::Colorize,
+        fuzzy::{fuzzy_match, highlight},
+        git::{self, ForgeTarget},
+        path, platform,
     },
-    helpers::{git, path, platform},
 };
 use inquire::Select;
-use std::collections::HashSet;
+use log::{debug, error};
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+use std::io::IsTerminal;
 use std::path::Path;
 
+use super::picker;
 use super::printer::error::handle_inquire_error;
+use super::printer::format::{OutputFormat, Tabular, print_rows};
 
 use std::fmt::{Display, Formatter, Result as FmtResult};
 
 const BRANCH_PADDING: usize = 2;
+/// Drawn before a worktree listed below its main repository.
+const WORKTREE_PREFIX: &str = "└ ";
 
 #[derive(Clone, Debug)]
 pub struct FoundItem {
     pub file_path: String,
     pub branch: String,
     pub match_hint: Option<String>,
+    /// Char positions in the displayed path that matched the keywords.
+    pub highlights: Vec<usize>,
     pub display_label: Option<String>,
 }
 
@@ -35,19 +49,37 @@ impl Display for FoundItem {
     }
 }
 
-fn handle_result(item: &FoundItem) {
+fn handle_result(c: &Context, item: &FoundItem) {
     println!("Found: {}", item);
     platform::clipboard::copy_path(&item.file_path);
+    record_visit(c, &item.file_path);
 }
 
-fn print_found_item_path(item: &FoundItem) {
+fn print_found_item_path(c: &Context, item: &FoundItem) {
     println!("{}", item.file_path);
+    record_visit(c, &item.file_path);
+}
+
+/// Counts a visit to the repository containing `path` for frecency ranking.
+pub fn record_visit(c: &Context, path: &str) {
+    let mut visited = false;
+    let result = c.database_mut().update(|db| visited = db.record_visit(path));
+    if let Err(e) = result {
+        error!("Failed to save database: {}", e);
+    } else if !visited {
+        debug!("Not a tracked repository, visit ignored: {}", path);
+    }
 }
 
 /// Extracts a search term from the input.
-/// If the input looks like a git URL, extracts the fullname (owner/repo) from it.
-/// Otherwise, returns the original input as-is.
+/// If the input looks like a forge page or a git URL, extracts the fullname
+/// (owner/repo) from it. Otherwise, returns the original input as-is.
 fn extract_search_term(input: &str) -> String {
+    // Checked first: as a git URL, `https://github.com/o/r/tree/main` would
+    // give the owner `o/r/tree`.
+    if let Some(forge) = git::parse_forge_url(input) {
+        return format!("{}/{}", forge.owner, forge.repo);
+    }
     if let Some(parsed) = git::parse_git_url(input) {
         if git::remote_url_is_valid(&parsed) {
             return parsed.fullname;
@@ -56,6 +88,11 @@ fn extract_search_term(input: &str) -> String {
     input.to_string()
 }
 
+/// Applies [`extract_search_term`] to every whitespace-separated term.
+fn extract_search_terms(input: &str) -> String {
+    input.split_whitespace().map(extract_search_term).collect::<Vec<_>>().join(" ")
+}
+
 fn match_hint(
     repo: &crate::context::database::models::Repo,
     match_kind: MatchKind,
@@ -69,7 +106,10 @@ fn match_hint(
 
     match match_kind {
         MatchKind::PathContains => None,
-        MatchKind::RepoExact | MatchKind::RepoContains | MatchKind::RepoFuzzy => {
+        MatchKind::RepoExact
+        | MatchKind::RepoContains
+        | MatchKind::RepoFuzzy
+        | MatchKind::Subsequence => {
             if repo_folder_matches {
                 None
             } else {
@@ -82,9 +122,28 @@ fn match_hint(
         MatchKind::OwnerExact | MatchKind::OwnerContains | MatchKind::RemoteContains => {
             Some(format!("remote: {}/{}/{}", repo.host, repo.owner, repo.repo))
         }
+        MatchKind::Tag => Some(format!("tags: {}", repo.tags.join(", "))),
+        MatchKind::Filter => None,
     }
 }
 
+/// Finds the characters of `display_path` matched by `keywords`, looking
+/// below the first `skip` characters (the base dir) first.
+fn highlight_positions(display_path: &str, skip: usize, keywords: &str) -> Vec<usize> {
+    let tail: String = display_path.chars().skip(skip).collect();
+    let mut positions: Vec<usize> = keywords
+        .split_whitespace()
+        .filter_map(|keyword| match fuzzy_match(&tail, keyword) {
+            Some(m) => Some(m.positions.into_iter().map(|p| p + skip).collect::<Vec<_>>()),
+            None => fuzzy_match(display_path, keyword).map(|m| m.positions),
+        })
+        .flatten()
+        .collect();
+    positions.sort_unstable();
+    positions.dedup();
+    positions
+}
+
 /// Builds the left-side label shown in selection lists, including any match hint.
 fn build_left_label(display_path: &str, match_hint: Option<&str>) -> String {
     if let Some(hint) = match_hint.filter(|hint| !hint.trim().is_empty()) {
@@ -118,74 +177,190 @@ fn format_display_line_with_len(
 pub fn find_keyword(c: &Context, keyword: &str) -> Option<Vec<FoundItem>> {
     c.auto_sync_silent();
 
-    let search_term = extract_search_term(keyword);
-    let result: Vec<MatchedRepo> = c.database_mut().find(&search_term);
-    if result.is_empty() {
-        return None;
+    let (options, has_missing) = collect_dirs(c, keyword);
+    if has_missing {
+        c.sync_silent();
+    }
+
+    if options.is_empty() { None } else { Some(options) }
+}
+
+/// Like [`collect_items`], but when nothing matches `keyword` its last term is
+/// also read as a path inside a repository: `prog/src/commands` and
+/// `prog src/commands` both resolve to the `src/commands` directory of `prog`.
+fn collect_dirs(c: &Context, keyword: &str) -> (Vec<FoundItem>, bool) {
+    let (items, mut has_missing) = collect_items(c, keyword);
+    if !items.is_empty() {
+        return (items, has_missing);
+    }
+
+    for (query, rest) in subpath_splits(keyword) {
+        let (items, missing) = collect_items(c, &query);
+        has_missing |= missing;
+        let found: Vec<FoundItem> = items
+            .into_iter()
+            .filter_map(|item| {
+                let dir = Path::new(&item.file_path).join(&rest);
+                dir.is_dir().then(|| FoundItem {
+                    file_path: dir.to_string_lossy().to_string(),
+                    highlights: Vec::new(),
+                    display_label: None,
+                    ..item
+                })
+            })
+            .collect();
+        if !found.is_empty() {
+            return (found, has_missing);
+        }
+    }
+
+    (Vec::new(), has_missing)
+}
+
+/// Ways to split `keyword` into a repository query and a path inside the
+/// repository, longest query first. Paths leaving the repository are skipped.
+fn subpath_splits(keyword: &str) -> Vec<(String, String)> {
+    let mut terms: Vec<&str> = keyword.split_whitespace().collect();
+    let Some(last) = terms.pop() else {
+        return Vec::new();
+    };
+    let head = terms.join(" ");
+    let segments: Vec<&str> = last.split('/').filter(|s| !s.is_empty()).collect();
+    let first = if head.is_empty() { 1 } else { 0 };
+
+    (first..segments.len())
+        .rev()
+        .filter(|&i| !segments[i..].contains(&".."))
+        .map(|i| {
+            let query = [head.as_str(), &segments[..i].join("/")].join(" ").trim().to_string();
+            (query, segments[i..].join("/"))
+        })
+        .collect()
+}
+
+/// Completes the last of `words`, the arguments given to `p`, with the
+/// directories inside the repositories the words before it resolve to.
+pub fn complete(c: &Context, words: &[String]) -> Vec<String> {
+    let Some((current, head)) = words.split_last() else {
+        return Vec::new();
+    };
+    let (dir_part, partial) = match current.rsplit_once('/') {
+        Some((dir, partial)) => (Some(dir), partial),
+        None => (None, current.as_str()),
+    };
+    let query: Vec<&str> = head.iter().map(String::as_str).chain(dir_part).collect();
+    let query = query.join(" ");
+    if query.trim().is_empty() {
+        return Vec::new();
     }
 
+    let prefix = dir_part.map(|dir| format!("{}/", dir)).unwrap_or_default();
+    let mut candidates: Vec<String> = collect_dirs(c, &query)
+        .0
+        .iter()
+        .filter_map(|item| std::fs::read_dir(&item.file_path).ok())
+        .flat_map(|entries| entries.flatten())
+        .filter(|entry| entry.path().is_dir())
+        .map(|entry| entry.file_name().to_string_lossy().to_string())
+        .filter(|name| {
+            name.starts_with(partial) && (!name.starts_with('.') || partial.starts_with('.'))
+        })
+        .map(|name| format!("{}{}/", prefix, name))
+        .collect();
+    candidates.sort();
+    candidates.dedup();
+    candidates
+}
+
+/// Builds the selectable entries for `keyword`. The flag is set when a
+/// matched repository no longer exists on disk, meaning the index is stale.
+pub fn collect_items(c: &Context, keyword: &str) -> (Vec<FoundItem>, bool) {
+    let search_term = extract_search_terms(keyword);
+    let mut result: Vec<MatchedRepo> = c.database().find(&search_term, c.config().rank_by_frecency);
+    result.retain(|matched| !c.is_excluded(&matched.repo));
+    let keywords = Query::parse(&search_term).keywords();
+
     // Use Vec with HashSet for deduplication while preserving insertion order
     let mut options: Vec<FoundItem> = Vec::new();
     let mut seen: HashSet<String> = HashSet::new();
 
     let mut should_sync = false;
+    let mut worktree_of: HashMap<String, String> = HashMap::new();
     for matched in result {
         let repo = &matched.repo;
         let path_str: String = repo.full_path.clone();
         if path::exists(&path_str) {
+            if let Some(main) = &repo.worktree_of {
+                worktree_of.insert(path_str.clone(), main.clone());
+            }
+
             // Repo path entry with branch
             if seen.insert(path_str.clone()) {
                 options.push(FoundItem {
                     file_path: path_str.clone(),
                     branch: git::get_branch(&path_str),
                     match_hint: match_hint(repo, matched.match_kind, &path_str),
+                    highlights: highlight_positions(
+                        &path::contract_tilde(&path_str),
+                        path::contract_tilde(&repo.base_dir).chars().count(),
+                        &keywords,
+                    ),
                     display_label: None,
                 });
             }
 
             // Host directory entry (no branch)
-            if repo.host == keyword {
-                let host_path = repo.host_fs_path();
-                if seen.insert(host_path.clone()) {
-                    options.push(FoundItem {
-                        file_path: host_path,
-                        branch: String::new(),
-                        match_hint: None,
-                        display_label: None,
-                    });
-                }
+            if repo.host == keyword
+                && let Some(host_path) = repo.host_fs_path(&c.config().layout)
+                && path::exists(&host_path)
+                && seen.insert(host_path.clone())
+            {
+                options.push(FoundItem {
+                    file_path: host_path,
+                    branch: String::new(),
+                    match_hint: None,
+                    highlights: Vec::new(),
+                    display_label: None,
+                });
             }
 
             // Owner directory entry (no branch)
-            if repo.owner == keyword {
-                let owner_path = repo.owner_fs_path();
-                if seen.insert(owner_path.clone()) {
-                    options.push(FoundItem {
-                        file_path: owner_path,
-                        branch: String::new(),
-                        match_hint: None,
-                        display_label: None,
-                    });
-                }
+            if repo.owner == keyword
+                && let Some(owner_path) = repo.owner_fs_path(&c.config().layout)
+                && path::exists(&owner_path)
+                && seen.insert(owner_path.clone())
+            {
+                options.push(FoundItem {
+                    file_path: owner_path,
+                    branch: String::new(),
+                    match_hint: None,
+                    highlights: Vec::new(),
+                    display_label: None,
+                });
             }
         } else {
             should_sync = true;
         }
     }
 
-    if should_sync {
-        c.sync_silent();
-    }
+    let nested = group_worktrees(&mut options, &worktree_of);
 
     let mut max_width = 0;
     let display_paths: Vec<(String, usize)> = options
         .iter()
         .map(|item| {
-            let label = build_left_label(
-                &path::contract_tilde(&item.file_path),
+            let display_path = path::contract_tilde(&item.file_path);
+            // Alignment uses the plain label; highlighting only adds escape codes.
+            let mut label_len =
+                build_left_label(&display_path, item.match_hint.as_deref()).chars().count();
+            let mut label = build_left_label(
+                &highlight(&display_path, &item.highlights),
                 item.match_hint.as_deref(),
             );
-            let label_len = label.chars().count();
+            if nested.contains(&item.file_path) {
+                label = format!("{}{}", WORKTREE_PREFIX, label);
+                label_len += WORKTREE_PREFIX.chars().count();
+            }
             max_width = max_width.max(label_len);
             (label, label_len)
         })
@@ -195,62 +370,368 @@ pub fn find_keyword(c: &Context, keyword: &str) -> Option<Vec<FoundItem>> {
             Some(format_display_line_with_len(&display_path, &item.branch, max_width, label_len));
     }
 
-    Some(options)
+    (options, should_sync)
 }
 
-pub fn run(c: &Context, keyword: &str, _query: bool) {
-    if _query {
-        query(&c, &keyword);
-    } else {
-        find(&c, &keyword);
+/// Moves every worktree whose main repository is among `options` right below
+/// it, keeping the order otherwise. Returns the paths of the moved worktrees.
+fn group_worktrees(
+    options: &mut Vec<FoundItem>,
+    worktree_of: &HashMap<String, String>,
+) -> HashSet<String> {
+    let listed: HashSet<&String> = options.iter().map(|item| &item.file_path).collect();
+    let nested: HashSet<String> = worktree_of
+        .iter()
+        .filter(|(_, main)| listed.contains(main))
+        .map(|(path, _)| path.clone())
+        .collect();
+    if nested.is_empty() {
+        return nested;
     }
+
+    let (worktrees, rest): (Vec<FoundItem>, Vec<FoundItem>) =
+        std::mem::take(options).into_iter().partition(|item| nested.contains(&item.file_path));
+    for item in rest {
+        let main = item.file_path.clone();
+        options.push(item);
+        options.extend(
+            worktrees.iter().filter(|wt| worktree_of.get(&wt.file_path) == Some(&main)).cloned(),
+        );
+    }
+    nested
 }
 
-pub fn query(c: &Context, keyword: &str) {
-    let result = find_keyword(c, keyword).unwrap_or_default();
+/// Resolves a repository given by path or keyword to its record, prompting
+/// when the keyword matches several repositories.
+pub fn select_repo(c: &Context, keyword: &str) -> Option<Repo> {
+    // A path to (or inside) a tracked repository wins over keyword search.
+    let target = path::expand_path(keyword);
+    if target.exists() {
+        let target = std::path::absolute(&target).unwrap_or(target);
+        let found = target
+            .ancestors()
+            .find_map(|ancestor| c.database().get_by_path(&ancestor.to_string_lossy()));
+        if found.is_some() {
+            return found;
+        }
+    }
 
-    if result.is_empty() {
-        return;
+    let options: Vec<FoundItem> = find_keyword(c, keyword)
+        .unwrap_or_default()
+        .into_iter()
+        .filter(|item| c.database().get_by_path(&item.file_path).is_some())
+        .collect();
+
+    let choice = match options.len() {
+        0 => return None,
+        1 => options.into_iter().next()?,
+        _ => match Select::new("Which project do you mean?", options).prompt() {
+            Ok(choice) => choice,
+            Err(e) => {
+                handle_inquire_error(e);
+                return None;
+            }
+        },
+    };
+
+    c.database().get_by_path(&choice.file_path)
+}
+
+/// A candidate in `find --format` output.
+#[derive(Serialize)]
+struct FindEntry<'a> {
+    path: &'a str,
+    host: &'a str,
+    owner: &'a str,
+    repo: &'a str,
+    remote_url: &'a str,
+    tags: &'a [String],
+    match_kind: &'static str,
+    /// Combined rank of every keyword, lower is better.
+    rank: u32,
+    /// Subsequence score, higher is better.
+    score: i64,
+}
+
+impl<'a> From<&'a MatchedRepo> for FindEntry<'a> {
+    fn from(matched: &'a MatchedRepo) -> Self {
+        let repo = &matched.repo;
+        FindEntry {
+            path: &repo.full_path,
+            host: &repo.host,
+            owner: &repo.owner,
+            repo: &repo.repo,
+            remote_url: &repo.remote_url,
+            tags: &repo.tags,
+            match_kind: matched.match_kind.as_str(),
+            rank: matched.rank,
+            score: matched.score,
+        }
     }
+}
 
-    if result.len() == 1 {
-        print_found_item_path(&result[0]);
-        return;
+impl Tabular for FindEntry<'_> {
+    const COLUMNS: &'static [&'static str] =
+        &["path", "host", "owner", "repo", "remote_url", "tags", "match_kind", "rank", "score"];
+
+    fn cells(&self) -> Vec<String> {
+        vec![
+            self.path.to_string(),
+            self.host.to_string(),
+            self.owner.to_string(),
+            self.repo.to_string(),
+            self.remote_url.to_string(),
+            self.tags.join(","),
+            self.match_kind.to_string(),
+            self.rank.to_string(),
+            self.score.to_string(),
+        ]
+    }
+}
+
+/// Prints every candidate for `keyword`, best first, without prompting.
+fn print_matches(c: &Context, keyword: &str, format: OutputFormat) {
+    c.auto_sync_silent();
+
+    let search_term = extract_search_terms(keyword);
+    let rank_by_frecency = c.config().rank_by_frecency;
+    let mut matches = c.database().find(&search_term, rank_by_frecency);
+    if matches.iter().any(|matched| !path::exists(&matched.repo.full_path)) {
+        c.sync_silent();
+        matches = c.database().find(&search_term, rank_by_frecency);
     }
+    matches.retain(|matched| !c.is_excluded(&matched.repo));
 
-    let ans = Select::new("Which project are you looking for?", result.clone()).prompt();
+    let entries: Vec<FindEntry> = matches.iter().map(FindEntry::from).collect();
+    print_rows(format, &entries);
+}
+
+/// How `find` turns its matches into the one directory it opens.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct FindOptions {
+    /// Switch to the branch a forge URL refers to.
+    pub checkout: bool,
+    /// Show the chooser even when a choice was remembered for the keyword.
+    pub choose_again: bool,
+    /// Open a submodule of the repository instead of the repository itself.
+    pub submodule: bool,
+}
 
-    match ans {
-        Ok(choice) => {
-            print_found_item_path(&choice);
+pub fn run(
+    c: &Context,
+    keyword: &str,
+    _query: bool,
+    format: Option<OutputFormat>,
+    options: FindOptions,
+) {
+    if let Some(format) = format {
+        print_matches(c, keyword, format);
+    } else if _query {
+        query(&c, &keyword, options);
+    } else {
+        find(&c, &keyword, options);
+    }
+}
+
+pub fn query(c: &Context, keyword: &str, options: FindOptions) {
+    if let Some(choice) = pick(c, keyword, options) {
+        print_found_item_path(c, &choice);
+    }
+}
+
+pub fn find(c: &Context, keyword: &str, options: FindOptions) -> bool {
+    match pick(c, keyword, options) {
+        Some(choice) => {
+            handle_result(c, &choice);
+            true
         }
-        Err(e) => handle_inquire_error(e),
+        None => false,
     }
 }
 
-pub fn find(c: &Context, keyword: &str) -> bool {
-    let result = find_keyword(c, keyword).unwrap_or_default();
+/// Finds `keyword` and lets the user choose when there are several results.
+/// A choice made for the same keyword and the same results is reused unless
+/// `choose_again` is set.
+fn pick(c: &Context, keyword: &str, options: FindOptions) -> Option<FoundItem> {
+    let mut result = find_keyword(c, keyword).unwrap_or_default();
+
+    let choice = match result.len() {
+        0 => return None,
+        1 => result.remove(0),
+        _ => choose_learned(c, keyword, result, options.choose_again)?,
+    };
+    if options.submodule {
+        return choose_submodule(c, &choice);
+    }
+    Some(open_forge_target(c, keyword, choice, options.checkout))
+}
 
-    if result.is_empty() {
-        return false;
+/// Lets the user choose among the checked out submodules of the repository
+/// in `item`.
+fn choose_submodule(c: &Context, item: &FoundItem) -> Option<FoundItem> {
+    let Some(repo) = c.database().get_by_path(&item.file_path) else {
+        eprintln!("{}", format!("Not a repository: {}", item.file_path).red());
+        return None;
+    };
+    let mut submodules: Vec<FoundItem> = repo
+        .submodules
+        .iter()
+        .filter(|submodule| submodule.is_initialized(&repo.full_path))
+        .map(|submodule| {
+            let file_path = submodule.full_path(&repo.full_path);
+            FoundItem {
+                branch: git::get_branch(&file_path),
+                file_path,
+                match_hint: None,
+                highlights: Vec::new(),
+                display_label: None,
+            }
+        })
+        .collect();
+
+    match submodules.len() {
+        0 => {
+            eprintln!("{}", format!("No submodules checked out in: {}", repo.full_path).red());
+            None
+        }
+        1 => Some(submodules.remove(0)),
+        _ => match Select::new("Which submodule?", submodules).prompt() {
+            Ok(choice) => Some(choice),
+            Err(e) => {
+                handle_inquire_error(e);
+                None
+            }
+        },
     }
+}
 
-    if result.len() == 1 {
-        handle_result(&result[0]);
-        return true;
+/// When `keyword` is a forge web URL, moves from the repository in `item` to
+/// the directory the URL points at, switching to its branch with `checkout`.
+fn open_forge_target(c: &Context, keyword: &str, item: FoundItem, checkout: bool) -> FoundItem {
+    let url = git::parse_forge_url(keyword).filter(|_| keyword.split_whitespace().count() == 1);
+    let (Some(url), Some(repo)) = (url, c.database().get_by_path(&item.file_path)) else {
+        if checkout {
+            eprintln!(
+                "{}",
+                "Nothing to check out: not a branch, file or pull request URL".yellow()
+            );
+        }
+        return item;
+    };
+    let root = repo.full_path.as_str();
+
+    let subpath = match &url.target {
+        ForgeTarget::Tree(segments) => {
+            let (name, path) =
+                git::split_ref_path(segments, &git::ref_names(root)).unwrap_or_default();
+            if checkout && !name.is_empty() {
+                report_checkout(&name, git::switch(root, &name));
+            }
+            path
+        }
+        ForgeTarget::PullRequest { number, refspec } => {
+            if checkout {
+                checkout_pull_request(&repo, &url, *number, refspec.as_deref());
+            }
+            String::new()
+        }
+        ForgeTarget::Page => String::new(),
+    };
+
+    // A file, or a directory missing on the current branch, opens the
+    // closest directory that exists.
+    let target = Path::new(root).join(subpath.trim_matches('/'));
+    let target = target.components().as_path();
+    let dir = target
+        .ancestors()
+        .take_while(|dir| dir.starts_with(root))
+        .find(|dir| dir.is_dir())
+        .unwrap_or(Path::new(root));
+    FoundItem {
+        file_path: dir.to_string_lossy().to_string(),
+        branch: git::get_branch(root),
+        highlights: Vec::new(),
+        display_label: None,
+        ..item
+    }
+}
+
+/// Fetches the head of a pull request into a `pr-<number>` branch and switches to it.
+fn checkout_pull_request(repo: &Repo, url: &git::ForgeUrl, number: u64, refspec: Option<&str>) {
+    let Some(refspec) = refspec else {
+        eprintln!("{}", format!("{} does not publish pull request refs", url.host).yellow());
+        return;
+    };
+    // Fetch from the remote the URL belongs to, which for a fork is its upstream.
+    let remote = repo
+        .remotes
+        .iter()
+        .find(|r| {
+            r.host.eq_ignore_ascii_case(&url.host)
+                && r.owner.eq_ignore_ascii_case(&url.owner)
+                && r.repo.eq_ignore_ascii_case(&url.repo)
+        })
+        .or(repo.remotes.first())
+        .map_or("origin", |r| r.name.as_str());
+    let branch = format!("pr-{}", number);
+
+    let result = git::fetch(&repo.full_path, remote, &format!("{}:{}", refspec, branch))
+        .and_then(|_| git::switch(&repo.full_path, &branch));
+    report_checkout(&branch, result);
+}
+
+fn report_checkout(name: &str, result: anyhow::Result<()>) {
+    match result {
+        Ok(()) => eprintln!("{}", format!("Switched to {}", name).green()),
+        Err(e) => eprintln!("{}", format!("Could not switch to {}: {}", name, e).red()),
     }
+}
 
-    let ans = Select::new("Which project are you looking for?", result.clone()).prompt();
+/// Reuses the result picked the last time `keyword` gave the same
+/// candidates, unless `choose_again` is set. Otherwise lets the user choose
+/// and remembers the pick for next time.
+fn choose_learned(
+    c: &Context,
+    keyword: &str,
+    items: Vec<FoundItem>,
+    choose_again: bool,
+) -> Option<FoundItem> {
+    let candidates: Vec<String> = items.iter().map(|item| item.file_path.clone()).collect();
+    if !choose_again {
+        let learned = c.database().learned_choice(keyword, &candidates);
+        if let Some(item) = learned.and_then(|path| items.iter().find(|i| i.file_path == path)) {
+            debug!("Reusing the choice learned for {}: {}", keyword, item.file_path);
+            return Some(item.clone());
+        }
+    }
 
-    match ans {
-        Ok(choice) => {
-            handle_result(&choice);
-            return true;
+    let choice = choose(c, keyword, items)?;
+    // The picker lets the query be edited, so only a pick among the results of
+    // `keyword` is an answer to it.
+    if candidates.contains(&choice.file_path) {
+        let result =
+            c.database_mut().update(|db| db.learn_choice(keyword, candidates, &choice.file_path));
+        if let Err(e) = result {
+            error!("Failed to save database: {}", e);
         }
-        Err(e) => handle_inquire_error(e),
+    }
+    Some(choice)
+}
+
+/// Lets the user pick one of several results: the live picker on a
+/// terminal, a plain selection list otherwise.
+fn choose(c: &Context, keyword: &str, items: Vec<FoundItem>) -> Option<FoundItem> {
+    if std::io::stderr().is_terminal() {
+        return picker::pick(c, keyword, items);
     }
 
-    false
+    match Select::new("Which project are you looking for?", items).prompt() {
+        Ok(choice) => Some(choice),
+        Err(e) => {
+            handle_inquire_error(e);
+            None
+        }
+    }
 }
 
 #[cfg(test)]
@@ -309,6 +790,65 @@ mod tests {
         assert_eq!(result, "git@github.com:owner");
     }
 
+    #[test]
+    fn test_forge_page_urls_find_their_repository() {
+        let mut db = crate::context::database::Database::in_memory();
+        for name in ["api", "web"] {
+            db.record_item(
+                "/base",
+                &format!("https://github.com/acme/{}.git", name),
+                "github.com",
+                name,
+                "acme",
+                &format!("/base/github.com/acme/{}", name),
+            );
+        }
+
+        for url in [
+            "https://github.com/acme/api/tree/main/src",
+            "https://github.com/acme/api/pull/42",
+            "https://github.com/acme/api/blob/feature/x/README.md#L10",
+        ] {
+            let found = db.find(&extract_search_terms(url), false);
+            let paths: Vec<&str> = found.iter().map(|m| m.repo.full_path.as_str()).collect();
+            assert_eq!(paths, vec!["/base/github.com/acme/api"], "{}", url);
+        }
+    }
+
+    #[test]
+    fn test_extract_search_terms_per_term() {
+        let result = extract_search_terms("https://github.com/bytemain/prog.git  owner:acme");
+        assert_eq!(result, "bytemain/prog owner:acme");
+    }
+
+    fn pair(query: &str, rest: &str) -> (String, String) {
+        (query.to_string(), rest.to_string())
+    }
+
+    #[test]
+    fn test_subpath_splits_single_term() {
+        assert_eq!(
+            subpath_splits("prog/src/commands/"),
+            vec![pair("prog/src", "commands"), pair("prog", "src/commands")]
+        );
+        assert!(subpath_splits("prog").is_empty());
+        assert!(subpath_splits("").is_empty());
+    }
+
+    #[test]
+    fn test_subpath_splits_separate_path_term() {
+        assert_eq!(
+            subpath_splits("acme prog src/commands"),
+            vec![pair("acme prog src", "commands"), pair("acme prog", "src/commands")]
+        );
+    }
+
+    #[test]
+    fn test_subpath_splits_stay_inside_repo() {
+        assert_eq!(subpath_splits("prog/../etc"), vec![pair("prog/..", "etc")]);
+        assert!(subpath_splits("prog ..").is_empty());
+    }
+
     #[test]
     fn test_match_hint_owner_match_includes_remote() {
         let now = chrono::Utc::now().naive_utc();
@@ -322,6 +862,7 @@ mod tests {
             base_dir: "/base".to_string(),
             // Non-standard path to mirror repositories cloned outside owner/repo structure.
             full_path: "/base/pyenv-versions".to_string(),
+            ..Default::default()
         };
 
         let hint = match_hint(&repo, MatchKind::OwnerExact, &repo.full_path);
@@ -329,18 +870,53 @@ mod tests {
         assert_eq!(hint, Some("remote: github.com/version-fox/versa-vault".to_string()));
     }
 
+    #[test]
+    fn test_highlight_positions_skip_base_dir() {
+        // "work" also appears in the base dir, but the match below it wins.
+        let positions = highlight_positions("~/work/github.com/acme/workbench", 6, "work");
+        assert_eq!(positions, vec![23, 24, 25, 26]);
+
+        let positions = highlight_positions("~/work/github.com/acme/prog-cli", 6, "prgcli");
+        assert_eq!(positions, vec![23, 24, 26, 28, 29, 30]);
+
+        assert!(highlight_positions("~/work/a/b", 6, "#infra").is_empty());
+    }
+
     #[test]
     fn test_found_item_display_with_hint() {
         let item = FoundItem {
             file_path: "/tmp/repo".to_string(),
             branch: "main".to_string(),
             match_hint: Some("repo: prog".to_string()),
+            highlights: Vec::new(),
             display_label: None,
         };
 
         assert_eq!(format!("{}", item), "/tmp/repo (repo: prog)  [main]");
     }
 
+    #[test]
+    fn test_group_worktrees_under_main() {
+        let item = |path: &str| FoundItem {
+            file_path: path.to_string(),
+            branch: String::new(),
+            match_hint: None,
+            highlights: Vec::new(),
+            display_label: None,
+        };
+        let mut options = vec![item("/w/api@fix"), item("/w/web"), item("/w/api"), item("/w/x@y")];
+        let worktree_of = HashMap::from([
+            ("/w/api@fix".to_string(), "/w/api".to_string()),
+            ("/w/x@y".to_string(), "/w/x".to_string()),
+        ]);
+
+        let nested = group_worktrees(&mut options, &worktree_of);
+
+        let paths: Vec<&str> = options.iter().map(|item| item.file_path.as_str()).collect();
+        assert_eq!(paths, vec!["/w/web", "/w/api", "/w/api@fix", "/w/x@y"]);
+        assert_eq!(nested, HashSet::from(["/w/api@fix".to_string()]));
+    }
+
     #[test]
     fn test_match_hint_repo_name_matches_folder_skips_hint() {
         let now = chrono::Utc::now().naive_utc();
@@ -353,6 +929,7 @@ mod tests {
             remote_url: "https://github.com/bytemain/prog.git".to_string(),
             base_dir: "/base".to_string(),
             full_path: "/base/github.com/bytemain/prog".to_string(),
+            ..Default::default()
         };
 
         let hint = match_hint(&repo, MatchKind::RepoExact, &repo.full_path);
@@ -0,0 +1,17 @@
+use crate::context::Context;
+use crate::helpers::colors::Colorize;
+
+pub fn run(c: &Context, keyword: &str) {
+    let keyword = Some(keyword).filter(|keyword| !keyword.trim().is_empty());
+    let mut forgotten = 0;
+    if let Err(e) = c.database_mut().update(|db| forgotten = db.forget_choices(keyword)) {
+        eprintln!("{}", format!("Failed to save database: {}", e).red());
+        return;
+    }
+
+    match (keyword, forgotten) {
+        (Some(keyword), 0) => println!("No choice remembered for: {}", keyword),
+        (Some(keyword), _) => println!("{}", format!("Forgot the choice for: {}", keyword).green()),
+        (None, n) => println!("{}", format!("Forgot {} remembered choices", n).green()),
+    }
+}
@@ -1,6 +1,7 @@
 use super::add;
 use crate::context::Context;
-use crate::helpers::git::get_remote_url;
+use crate::context::database::models::{RepoRemote, primary_remote};
+use crate::helpers::git::get_remotes;
 use crate::helpers::path::expand_path;
 use log::info;
 use std::path::PathBuf;
@@ -11,8 +12,18 @@ pub fn run(c: &mut Context, path: PathBuf) {
     let path = expand_path(path.to_str().unwrap());
     if path.exists() {
         info!("path exists, trying to clone");
-        let remote_url = get_remote_url(path.to_str().unwrap());
-        add::run(c, &remote_url, &[]);
+        let remotes: Vec<RepoRemote> = get_remotes(path.to_str().unwrap())
+            .iter()
+            .map(|(name, url)| RepoRemote::new(name, url))
+            .collect();
+        let url = primary_remote(&remotes, &c.config().preferred_remotes).map(|r| r.url.clone());
+        match url {
+            Some(url) => add::run(c, &url, None, &[]),
+            None => {
+                eprintln!("No remote with a valid git url found in {}", path.display());
+                exit(1);
+            }
+        }
         return;
     }
 
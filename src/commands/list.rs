@@ -1,12 +1,80 @@
+use super::printer::format::{OutputFormat, Tabular, print_rows};
+use crate::commands::tag::format_tags;
 use crate::context::core::Context;
-use crate::context::database::models::Repo;
+use crate::context::database::Query;
+use crate::context::database::models::{LOCAL_HOST, Repo};
 use crate::helpers::colors::Colorize;
+use crate::helpers::git::{Submodule, get_submodules};
+use serde::Serialize;
 use std::collections::HashMap;
 
-pub fn run(c: &mut Context) {
+/// A repository in `list --format` output.
+#[derive(Serialize)]
+struct ListEntry<'a> {
+    path: &'a str,
+    base_dir: &'a str,
+    host: &'a str,
+    owner: &'a str,
+    repo: &'a str,
+    remote_url: &'a str,
+    tags: &'a [String],
+}
+
+impl<'a> From<&'a Repo> for ListEntry<'a> {
+    fn from(repo: &'a Repo) -> Self {
+        ListEntry {
+            path: &repo.full_path,
+            base_dir: &repo.base_dir,
+            host: &repo.host,
+            owner: &repo.owner,
+            repo: &repo.repo,
+            remote_url: &repo.remote_url,
+            tags: &repo.tags,
+        }
+    }
+}
+
+impl Tabular for ListEntry<'_> {
+    const COLUMNS: &'static [&'static str] =
+        &["path", "base_dir", "host", "owner", "repo", "remote_url", "tags"];
+
+    fn cells(&self) -> Vec<String> {
+        vec![
+            self.path.to_string(),
+            self.base_dir.to_string(),
+            self.host.to_string(),
+            self.owner.to_string(),
+            self.repo.to_string(),
+            self.remote_url.to_string(),
+            self.tags.join(","),
+        ]
+    }
+}
+
+pub fn run(c: &mut Context, tags: &[String], query: &str, format: Option<OutputFormat>) {
     c.auto_sync_silent();
 
-    let items = c.database_mut().get_all_items();
+    let query = Query::parse(query);
+    let mut items = c.database_mut().get_all_items();
+    items.retain(|item| {
+        tags.iter().all(|tag| item.has_tag(tag)) && query.matches(item) && !c.is_excluded(item)
+    });
+
+    if let Some(format) = format {
+        // Same order as the grouped output below.
+        items.sort_by(|a, b| {
+            (&a.base_dir, a.host == LOCAL_HOST, &a.host, &a.full_path).cmp(&(
+                &b.base_dir,
+                b.host == LOCAL_HOST,
+                &b.host,
+                &b.full_path,
+            ))
+        });
+        let entries: Vec<ListEntry> = items.iter().map(ListEntry::from).collect();
+        print_rows(format, &entries);
+        return;
+    }
+
     // Group by base_dir, then by host
     let mut grouped_by_base_dir: HashMap<String, HashMap<String, Vec<Repo>>> = HashMap::new();
 
@@ -28,12 +96,15 @@ pub fn run(c: &mut Context) {
         if let Some(workspaces_in_host) = grouped_by_base_dir.get(&base_dir) {
             println!("{}", base_dir.green()); // Print host name in green
 
+            // Repositories without a remote come last, in a group of their own.
             let mut workspace_keys: Vec<_> = workspaces_in_host.keys().cloned().collect();
-            workspace_keys.sort();
+            workspace_keys.sort_by(|a, b| (a == LOCAL_HOST, a).cmp(&(b == LOCAL_HOST, b)));
 
             for ws_key in workspace_keys {
                 if let Some(repo_items_in_ws) = workspaces_in_host.get(&ws_key) {
-                    if !ws_key.is_empty() {
+                    if ws_key == LOCAL_HOST {
+                        println!("  {}", "local (no remote)".blue());
+                    } else if !ws_key.is_empty() {
                         println!("  {}", ws_key.as_str().blue());
                     }
 
@@ -43,10 +114,32 @@ pub fn run(c: &mut Context) {
                     for repo_item in sorted_repo_items {
                         let indent = if ws_key.is_empty() { "  " } else { "    " };
                         let path_to_display = repo_item.full_path.clone();
-                        println!("{}{}", indent, path_to_display);
+                        if repo_item.tags.is_empty() {
+                            println!("{}{}", indent, path_to_display);
+                        } else {
+                            println!(
+                                "{}{}  {}",
+                                indent,
+                                path_to_display,
+                                format_tags(&repo_item.tags).blue()
+                            );
+                        }
+                        print_submodules(&repo_item.full_path, &repo_item.submodules, indent);
                     }
                 }
             }
         }
     }
 }
+
+/// Prints the `submodules` of `superproject` below it, and those of each
+/// checked out submodule in turn, one level deeper.
+fn print_submodules(superproject: &str, submodules: &[Submodule], indent: &str) {
+    for submodule in submodules {
+        println!("{}  └ {}", indent, submodule.path);
+        if submodule.is_initialized(superproject) {
+            let path = submodule.full_path(superproject);
+            print_submodules(&path, &get_submodules(&path), &format!("{}  ", indent));
+        }
+    }
+}
@@ -1,11 +1,23 @@
 pub mod add;
+pub mod apply;
 pub mod check;
 pub mod clean;
+pub mod daemon;
+pub mod db;
+pub mod doctor;
+pub mod export;
 pub mod find;
+pub mod forget;
 pub mod import;
 pub mod list;
+pub mod note;
+mod picker;
 pub mod remove;
 pub mod sync;
+pub mod tag;
 pub mod tmp;
+pub mod wt;
 
 mod printer;
+
+pub use printer::format::OutputFormat;
@@ -0,0 +1,33 @@
+use crate::commands::find::select_repo;
+use crate::context::Context;
+use crate::helpers::colors::Colorize;
+
+pub fn run(c: &mut Context, keyword: &str, text: Option<&str>, clear: bool) {
+    let Some(repo) = select_repo(c, keyword) else {
+        eprintln!("{}", format!("No repository found for: {}", keyword).red());
+        return;
+    };
+
+    // Without text (and without --clear) just show the current note.
+    if text.is_none() && !clear {
+        match repo.note {
+            Some(note) => println!("{}", note),
+            None => println!("No note for {}", repo.full_path),
+        }
+        return;
+    }
+
+    let note = if clear { None } else { text.map(str::to_string) };
+    if let Err(e) = c.database_mut().update_with_history("note", |db| {
+        db.set_note(&repo.full_path, note);
+    }) {
+        eprintln!("{}", format!("Failed to save database: {}", e).red());
+        return;
+    }
+
+    if clear {
+        println!("{}", format!("Note cleared: {}", repo.full_path).green());
+    } else {
+        println!("{}", format!("Note saved: {}", repo.full_path).green());
+    }
+}
@@ -0,0 +1,126 @@
+mod preview;
+mod render;
+mod state;
+
+use std::collections::HashMap;
+use std::io::{self, Stderr, Write};
+use std::path::Path;
+
+use crossterm::cursor::{Hide, Show};
+use crossterm::event::{self, Event, KeyEventKind};
+use crossterm::execute;
+use crossterm::terminal::{self, EnterAlternateScreen, LeaveAlternateScreen};
+
+use super::find::{FoundItem, collect_items};
+use super::remove::{remove_tracked_repo, tracked_repo};
+use crate::context::Context;
+use crate::helpers::colors::Colorize;
+use crate::helpers::platform::{clipboard, open};
+use state::{Action, Picker};
+
+/// Puts the terminal into raw mode on the alternate screen and restores it
+/// when dropped, even if the picker bails out early.
+struct TerminalGuard {
+    out: Stderr,
+}
+
+impl TerminalGuard {
+    fn enter() -> io::Result<Self> {
+        let mut out = io::stderr();
+        terminal::enable_raw_mode()?;
+        if let Err(e) = execute!(out, EnterAlternateScreen, Hide) {
+            let _ = terminal::disable_raw_mode();
+            return Err(e);
+        }
+        Ok(TerminalGuard { out })
+    }
+}
+
+impl Drop for TerminalGuard {
+    fn drop(&mut self) {
+        let _ = execute!(self.out, Show, LeaveAlternateScreen);
+        let _ = terminal::disable_raw_mode();
+    }
+}
+
+/// Opens the full-screen picker on stderr, so it also works while stdout is
+/// captured by the shell integration. Typing refines `query` live; returns
+/// the chosen entry, or `None` when cancelled.
+pub fn pick(c: &Context, query: &str, items: Vec<FoundItem>) -> Option<FoundItem> {
+    let result = TerminalGuard::enter().and_then(|mut guard| run(c, &mut guard.out, query, items));
+    match result {
+        Ok(choice) => choice,
+        Err(e) => {
+            eprintln!("{}", format!("Picker failed: {}", e).red());
+            None
+        }
+    }
+}
+
+fn run(
+    c: &Context,
+    out: &mut impl Write,
+    query: &str,
+    items: Vec<FoundItem>,
+) -> io::Result<Option<FoundItem>> {
+    let mut picker = Picker::new(query, items);
+    let mut previews: HashMap<String, Vec<String>> = HashMap::new();
+
+    loop {
+        let preview = match picker.selected_item() {
+            Some(item) => previews
+                .entry(item.file_path.clone())
+                .or_insert_with(|| {
+                    let repo = c.database().get_by_path(&item.file_path);
+                    preview::build(&item.file_path, repo.as_ref())
+                })
+                .as_slice(),
+            None => &[],
+        };
+        render::draw(out, &mut picker, preview, terminal::size()?)?;
+
+        let key = match event::read()? {
+            Event::Key(key) if key.kind != KeyEventKind::Release => key,
+            _ => continue,
+        };
+
+        match picker.handle_key(key) {
+            Action::None => {}
+            Action::Search => picker.show_results(collect_items(c, &picker.query).0),
+            Action::Accept => return Ok(picker.selected_item().cloned()),
+            Action::Cancel => return Ok(None),
+            Action::Copy => {
+                let path = picker.selected_item().map(|i| i.file_path.clone()).unwrap_or_default();
+                picker.status = Some(if clipboard::copy_text(&path) {
+                    format!("Copied {}", path)
+                } else {
+                    "Failed to copy to clipboard".to_string()
+                });
+            }
+            Action::Open => {
+                let path = picker.selected_item().map(|i| i.file_path.clone()).unwrap_or_default();
+                picker.status = Some(if open::open_path(&path) {
+                    format!("Opened {}", path)
+                } else {
+                    format!("Unable to open {}", path)
+                });
+            }
+            Action::AskRemove => {
+                let path = picker.selected_item().map(|i| i.file_path.clone()).unwrap_or_default();
+                match tracked_repo(&c.database(), &path) {
+                    Ok(_) => picker.confirm_remove(),
+                    Err(e) => picker.status = Some(e.to_string()),
+                }
+            }
+            Action::Remove => {
+                let path = picker.selected_item().map(|i| i.file_path.clone()).unwrap_or_default();
+                picker.status = Some(match remove_tracked_repo(c, Path::new(&path)) {
+                    Ok(()) => format!("Deleted {}", path),
+                    Err(e) => e.to_string(),
+                });
+                previews.remove(&path);
+                picker.set_items(collect_items(c, &picker.query).0);
+            }
+        }
+    }
+}
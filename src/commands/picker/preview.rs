@@ -0,0 +1,135 @@
+use std::fs;
+use std::path::Path;
+
+use crate::commands::tag::format_tags;
+use crate::context::database::models::Repo;
+use crate::helpers::git::{RepoStatus, get_repo_status, last_commit};
+use crate::helpers::path::contract_tilde;
+
+const README_LINES: usize = 40;
+
+/// Builds the preview pane for the entry at `path`: where it is, its git
+/// state, user metadata and the start of its README.
+pub(super) fn build(path: &str, repo: Option<&Repo>) -> Vec<String> {
+    let mut lines = vec![contract_tilde(path)];
+
+    if let Some(status) = get_repo_status(path) {
+        lines.extend(summarize_status(&status));
+    }
+    if let Some(commit) = last_commit(path) {
+        lines.push(format!("last commit: {}", commit));
+    }
+    if let Some(repo) = repo {
+        if !repo.tags.is_empty() {
+            lines.push(format!("tags: {}", format_tags(&repo.tags)));
+        }
+        if let Some(note) = repo.note.as_deref() {
+            lines.push(format!("note: {}", note));
+        }
+    }
+
+    let readme = readme_lines(Path::new(path), README_LINES);
+    if !readme.is_empty() {
+        lines.push(String::new());
+        lines.extend(readme);
+    }
+    lines
+}
+
+fn summarize_status(status: &RepoStatus) -> Vec<String> {
+    let mut branch = if status.detached {
+        "branch: (detached)".to_string()
+    } else {
+        format!("branch: {}", status.branch)
+    };
+    if status.ahead > 0 {
+        branch.push_str(&format!(" ↑{}", status.ahead));
+    }
+    if status.behind > 0 {
+        branch.push_str(&format!(" ↓{}", status.behind));
+    }
+    if !status.detached && status.upstream.is_none() {
+        branch.push_str(" (no upstream)");
+    }
+
+    let changes = if status.is_dirty() {
+        let counts = [
+            (status.modified, "modified"),
+            (status.untracked, "untracked"),
+            (status.conflicted, "conflicted"),
+        ];
+        let parts: Vec<String> = counts
+            .iter()
+            .filter(|(count, _)| *count > 0)
+            .map(|(count, label)| format!("{} {}", count, label))
+            .collect();
+        format!("changes: {}", parts.join(", "))
+    } else {
+        "changes: clean".to_string()
+    };
+
+    vec![branch, changes]
+}
+
+/// The first `max` lines of the README in `dir`, without leading blank lines.
+fn readme_lines(dir: &Path, max: usize) -> Vec<String> {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let mut candidates: Vec<_> = entries
+        .flatten()
+        .filter(|entry| entry.file_type().is_ok_and(|t| t.is_file()))
+        .map(|entry| entry.path())
+        .filter(|path| {
+            path.file_name()
+                .and_then(|name| name.to_str())
+                .is_some_and(|name| name.to_lowercase().starts_with("readme"))
+        })
+        .collect();
+    // Prefer README.md over README.zh-CN.md and friends.
+    candidates.sort_by_key(|path| path.as_os_str().len());
+
+    let Some(contents) = candidates.first().and_then(|path| fs::read_to_string(path).ok()) else {
+        return Vec::new();
+    };
+    contents
+        .lines()
+        .skip_while(|line| line.trim().is_empty())
+        .take(max)
+        .map(|line| line.replace('\t', "    "))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_summarize_status() {
+        let status = RepoStatus {
+            branch: "main".to_string(),
+            upstream: Some("origin/main".to_string()),
+            ahead: 2,
+            modified: 1,
+            untracked: 3,
+            ..Default::default()
+        };
+        assert_eq!(
+            summarize_status(&status),
+            vec!["branch: main ↑2", "changes: 1 modified, 3 untracked"]
+        );
+
+        let clean = RepoStatus { branch: "dev".to_string(), ..Default::default() };
+        assert_eq!(summarize_status(&clean), vec!["branch: dev (no upstream)", "changes: clean"]);
+    }
+
+    #[test]
+    fn test_readme_lines() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        fs::write(temp_dir.path().join("README.zh-CN.md"), "translated").unwrap();
+        fs::write(temp_dir.path().join("README.md"), "\n\n# prog\n\n\tindented\nthird\n").unwrap();
+
+        assert_eq!(readme_lines(temp_dir.path(), 3), vec!["# prog", "", "    indented"]);
+        assert!(readme_lines(&temp_dir.path().join("missing"), 3).is_empty());
+    }
+}
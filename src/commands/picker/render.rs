@@ -0,0 +1,140 @@
+use std::io::{self, Write};
+
+use crossterm::cursor::{MoveTo, Show};
+use crossterm::queue;
+use crossterm::style::Print;
+use crossterm::terminal::{Clear, ClearType};
+
+use super::state::Picker;
+use crate::commands::find::FoundItem;
+use crate::helpers::colors::Colorize;
+use crate::helpers::fuzzy::highlight;
+use crate::helpers::path::contract_tilde;
+
+const PROMPT: &str = "> ";
+const SEPARATOR: &str = " │ ";
+const HELP: &str = "enter select · ctrl-y copy · ctrl-o open · ctrl-x delete · esc quit";
+/// Below this width the preview pane is hidden.
+const MIN_PREVIEW_WIDTH: u16 = 80;
+
+/// Cuts `text` to at most `width` characters, marking the cut with `…`.
+fn fit(text: &str, width: usize) -> String {
+    if text.chars().count() <= width {
+        return text.to_string();
+    }
+    if width == 0 {
+        return String::new();
+    }
+    let mut cut: String = text.chars().take(width - 1).collect();
+    cut.push('…');
+    cut
+}
+
+/// One list row: the path with matched characters highlighted, the match
+/// hint and the branch, padded to exactly `width` columns.
+fn format_item(item: &FoundItem, width: usize) -> String {
+    let display_path = contract_tilde(&item.file_path);
+    let mut plain = display_path.clone();
+    if let Some(hint) = item.match_hint.as_deref() {
+        plain.push_str(&format!(" ({})", hint));
+    }
+    if !item.branch.is_empty() {
+        plain.push_str(&format!("  [{}]", item.branch));
+    }
+
+    let plain = fit(&plain, width);
+    let len = plain.chars().count();
+    // Highlights index into the path, which starts the row.
+    let path_len = display_path.chars().count().min(len);
+    let visible: Vec<usize> = item.highlights.iter().copied().filter(|&p| p < path_len).collect();
+    format!("{}{}", highlight(&plain, &visible), " ".repeat(width - len))
+}
+
+pub(super) fn draw(
+    out: &mut impl Write,
+    picker: &mut Picker,
+    preview: &[String],
+    (width, height): (u16, u16),
+) -> io::Result<()> {
+    let total_width = width as usize;
+    let rows = height.saturating_sub(2) as usize;
+    let (list_width, preview_width) = if width >= MIN_PREVIEW_WIDTH {
+        let list_width = total_width * 3 / 5;
+        (list_width, total_width - list_width - SEPARATOR.chars().count())
+    } else {
+        (total_width, 0)
+    };
+    picker.scroll_into_view(rows);
+
+    let count = format!(" {}", picker.items.len());
+    let query_width = total_width.saturating_sub(PROMPT.len() + count.len());
+    queue!(
+        out,
+        MoveTo(0, 0),
+        Print(PROMPT.blue()),
+        Print(fit(&picker.query, query_width)),
+        Clear(ClearType::UntilNewLine),
+        MoveTo(width.saturating_sub(count.len() as u16), 0),
+        Print(count.as_str().green())
+    )?;
+
+    for row in 0..rows {
+        let index = picker.offset + row;
+        let marker_width = PROMPT.len();
+        let item_width = list_width.saturating_sub(marker_width);
+        let line = match picker.items.get(index) {
+            Some(item) if index == picker.selected => {
+                format!("{}{}", PROMPT.blue(), format_item(item, item_width))
+            }
+            Some(item) => format!("{}{}", " ".repeat(marker_width), format_item(item, item_width)),
+            None => " ".repeat(list_width),
+        };
+        queue!(out, MoveTo(0, row as u16 + 1), Print(line))?;
+        if preview_width > 0 {
+            let text = preview.get(row).map(|l| fit(l, preview_width)).unwrap_or_default();
+            queue!(out, Print(SEPARATOR), Print(text))?;
+        }
+        queue!(out, Clear(ClearType::UntilNewLine))?;
+    }
+
+    let footer = match picker.status.as_deref() {
+        Some(status) => fit(status, total_width).yellow().to_string(),
+        None => fit(HELP, total_width),
+    };
+    queue!(
+        out,
+        MoveTo(0, height.saturating_sub(1)),
+        Print(footer),
+        Clear(ClearType::UntilNewLine)
+    )?;
+
+    let cursor = (PROMPT.len() + picker.query.chars().count()).min(total_width) as u16;
+    queue!(out, MoveTo(cursor, 0), Show)?;
+    out.flush()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fit_truncates_with_ellipsis() {
+        assert_eq!(fit("prog", 10), "prog");
+        assert_eq!(fit("github.com/bytemain/prog", 10), "github.co…");
+        assert_eq!(fit("prog", 0), "");
+    }
+
+    #[test]
+    fn test_format_item_pads_to_width() {
+        let item = FoundItem {
+            file_path: "/work/prog".to_string(),
+            branch: "main".to_string(),
+            match_hint: None,
+            highlights: Vec::new(),
+            display_label: None,
+        };
+
+        assert_eq!(format_item(&item, 22), "/work/prog  [main]    ");
+        assert_eq!(format_item(&item, 8), "/work/p…");
+    }
+}
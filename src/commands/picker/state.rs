@@ -0,0 +1,233 @@
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+
+use crate::commands::find::FoundItem;
+
+/// What the event loop should do after a key press.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) enum Action {
+    None,
+    /// The query changed, search again.
+    Search,
+    Accept,
+    Cancel,
+    Copy,
+    Open,
+    /// ctrl-x: the selection may be deleted, see [`Picker::confirm_remove`].
+    AskRemove,
+    Remove,
+}
+
+/// Query, results and selection of the picker, independent of the terminal.
+#[derive(Debug, Default)]
+pub(super) struct Picker {
+    pub query: String,
+    pub items: Vec<FoundItem>,
+    pub selected: usize,
+    /// Index of the first visible row.
+    pub offset: usize,
+    /// Message shown in the footer until the next key press.
+    pub status: Option<String>,
+    /// Set after ctrl-x while waiting for the removal to be confirmed.
+    pub confirm_remove: bool,
+}
+
+impl Picker {
+    pub fn new(query: &str, items: Vec<FoundItem>) -> Self {
+        Picker { query: query.to_string(), items, ..Default::default() }
+    }
+
+    /// Shows the results of a new query, starting from the best match.
+    pub fn show_results(&mut self, items: Vec<FoundItem>) {
+        self.selected = 0;
+        self.offset = 0;
+        self.items = items;
+    }
+
+    /// Replaces the results of the current query, keeping the selection.
+    pub fn set_items(&mut self, items: Vec<FoundItem>) {
+        self.items = items;
+        self.selected = self.selected.min(self.items.len().saturating_sub(1));
+        self.offset = self.offset.min(self.selected);
+    }
+
+    pub fn selected_item(&self) -> Option<&FoundItem> {
+        self.items.get(self.selected)
+    }
+
+    /// Scrolls so the selection is inside a window of `rows` rows.
+    pub fn scroll_into_view(&mut self, rows: usize) {
+        if rows == 0 {
+            return;
+        }
+        if self.selected < self.offset {
+            self.offset = self.selected;
+        } else if self.selected >= self.offset + rows {
+            self.offset = self.selected + 1 - rows;
+        }
+    }
+
+    fn move_by(&mut self, delta: isize) {
+        if self.items.is_empty() {
+            return;
+        }
+        let last = self.items.len() - 1;
+        self.selected = self.selected.saturating_add_signed(delta).min(last);
+    }
+
+    pub fn handle_key(&mut self, key: KeyEvent) -> Action {
+        self.status = None;
+        let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+
+        if self.confirm_remove {
+            self.confirm_remove = false;
+            return match key.code {
+                KeyCode::Char('y') | KeyCode::Char('Y') if !ctrl => Action::Remove,
+                _ => Action::None,
+            };
+        }
+
+        match key.code {
+            KeyCode::Esc => Action::Cancel,
+            KeyCode::Char('c') | KeyCode::Char('g') if ctrl => Action::Cancel,
+            KeyCode::Enter if self.items.is_empty() => Action::None,
+            KeyCode::Enter => Action::Accept,
+            KeyCode::Up => self.step(-1),
+            KeyCode::Char('p') | KeyCode::Char('k') if ctrl => self.step(-1),
+            KeyCode::Down | KeyCode::Tab => self.step(1),
+            KeyCode::Char('n') | KeyCode::Char('j') if ctrl => self.step(1),
+            KeyCode::PageUp => self.step(-10),
+            KeyCode::PageDown => self.step(10),
+            KeyCode::Char('y') if ctrl => self.with_selection(Action::Copy),
+            KeyCode::Char('o') if ctrl => self.with_selection(Action::Open),
+            KeyCode::Char('x') if ctrl => self.with_selection(Action::AskRemove),
+            KeyCode::Char('u') if ctrl => {
+                self.query.clear();
+                Action::Search
+            }
+            KeyCode::Char('w') if ctrl => {
+                let trimmed = self.query.trim_end();
+                let keep = trimmed.rfind(' ').map_or(0, |i| i + 1);
+                self.query.truncate(keep);
+                Action::Search
+            }
+            KeyCode::Backspace => {
+                self.query.pop();
+                Action::Search
+            }
+            KeyCode::Char(c) if !ctrl => {
+                self.query.push(c);
+                Action::Search
+            }
+            _ => Action::None,
+        }
+    }
+
+    /// Asks whether to delete the selection; a following `y` removes it.
+    pub fn confirm_remove(&mut self) {
+        if let Some(item) = self.selected_item() {
+            self.status = Some(format!("Delete {} from disk? [y/N]", item.file_path));
+            self.confirm_remove = true;
+        }
+    }
+
+    fn step(&mut self, delta: isize) -> Action {
+        self.move_by(delta);
+        Action::None
+    }
+
+    fn with_selection(&self, action: Action) -> Action {
+        if self.items.is_empty() { Action::None } else { action }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn item(path: &str) -> FoundItem {
+        FoundItem {
+            file_path: path.to_string(),
+            branch: String::new(),
+            match_hint: None,
+            highlights: Vec::new(),
+            display_label: None,
+        }
+    }
+
+    fn key(code: KeyCode) -> KeyEvent {
+        KeyEvent::new(code, KeyModifiers::NONE)
+    }
+
+    fn ctrl(c: char) -> KeyEvent {
+        KeyEvent::new(KeyCode::Char(c), KeyModifiers::CONTROL)
+    }
+
+    #[test]
+    fn test_typing_edits_query_and_searches() {
+        let mut picker = Picker::new("pr", Vec::new());
+
+        assert_eq!(picker.handle_key(key(KeyCode::Char('o'))), Action::Search);
+        assert_eq!(picker.query, "pro");
+        assert_eq!(picker.handle_key(key(KeyCode::Backspace)), Action::Search);
+        assert_eq!(picker.query, "pr");
+
+        picker.query = "api owner:acme".to_string();
+        assert_eq!(picker.handle_key(ctrl('w')), Action::Search);
+        assert_eq!(picker.query, "api ");
+        assert_eq!(picker.handle_key(ctrl('u')), Action::Search);
+        assert!(picker.query.is_empty());
+    }
+
+    #[test]
+    fn test_navigation_stays_in_bounds() {
+        let mut picker = Picker::new("", vec![item("/a"), item("/b"), item("/c")]);
+
+        picker.handle_key(key(KeyCode::Up));
+        assert_eq!(picker.selected, 0);
+        picker.handle_key(ctrl('n'));
+        picker.handle_key(key(KeyCode::Down));
+        picker.handle_key(key(KeyCode::Down));
+        assert_eq!(picker.selected, 2);
+        assert_eq!(picker.handle_key(key(KeyCode::Enter)), Action::Accept);
+        assert_eq!(picker.selected_item().unwrap().file_path, "/c");
+
+        picker.set_items(vec![item("/a")]);
+        assert_eq!(picker.selected, 0, "selection is clamped to the new results");
+    }
+
+    #[test]
+    fn test_scroll_into_view() {
+        let items = (0..10).map(|i| item(&format!("/{}", i))).collect();
+        let mut picker = Picker::new("", items);
+
+        picker.selected = 6;
+        picker.scroll_into_view(4);
+        assert_eq!(picker.offset, 3);
+        picker.selected = 1;
+        picker.scroll_into_view(4);
+        assert_eq!(picker.offset, 1);
+    }
+
+    #[test]
+    fn test_remove_needs_confirmation() {
+        let mut picker = Picker::new("", vec![item("/a")]);
+
+        assert_eq!(picker.handle_key(ctrl('x')), Action::AskRemove);
+        picker.confirm_remove();
+        assert!(picker.status.as_deref().unwrap().contains("/a"));
+        assert_eq!(picker.handle_key(key(KeyCode::Char('n'))), Action::None);
+        assert!(picker.status.is_none());
+
+        picker.confirm_remove();
+        assert_eq!(picker.handle_key(key(KeyCode::Char('y'))), Action::Remove);
+    }
+
+    #[test]
+    fn test_actions_need_a_selection() {
+        let mut picker = Picker::new("zzz", Vec::new());
+
+        assert_eq!(picker.handle_key(key(KeyCode::Enter)), Action::None);
+        assert_eq!(picker.handle_key(ctrl('y')), Action::None);
+        assert_eq!(picker.handle_key(key(KeyCode::Esc)), Action::Cancel);
+    }
+}
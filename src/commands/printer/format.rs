@@ -0,0 +1,111 @@
+use crate::helpers::colors::Colorize;
+use clap::ValueEnum;
+use serde::Serialize;
+
+/// Machine-readable output, selected with the global `--format` option.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum OutputFormat {
+    /// A single JSON document
+    Json,
+    /// One JSON object per line
+    Ndjson,
+    /// Tab-separated values with a header row
+    Tsv,
+}
+
+/// A record that can be written as a TSV row.
+pub trait Tabular {
+    /// Header row, in the same order as [`Tabular::cells`].
+    const COLUMNS: &'static [&'static str];
+
+    fn cells(&self) -> Vec<String>;
+}
+
+/// Prints `rows` in `format`: a JSON array, one JSON object per line, or TSV.
+pub fn print_rows<T: Serialize + Tabular>(format: OutputFormat, rows: &[T]) {
+    match render_rows(format, rows) {
+        Ok(output) => print!("{}", output),
+        Err(e) => eprintln!("{}", format!("Failed to serialize output: {}", e).red()),
+    }
+}
+
+fn render_rows<T: Serialize + Tabular>(
+    format: OutputFormat,
+    rows: &[T],
+) -> Result<String, serde_json::Error> {
+    let mut output = String::new();
+    match format {
+        OutputFormat::Json => {
+            output.push_str(&serde_json::to_string(rows)?);
+            output.push('\n');
+        }
+        OutputFormat::Ndjson => {
+            for row in rows {
+                output.push_str(&serde_json::to_string(row)?);
+                output.push('\n');
+            }
+        }
+        OutputFormat::Tsv => {
+            output.push_str(&T::COLUMNS.join("\t"));
+            output.push('\n');
+            for row in rows {
+                let cells: Vec<String> = row.cells().iter().map(|cell| tsv_cell(cell)).collect();
+                output.push_str(&cells.join("\t"));
+                output.push('\n');
+            }
+        }
+    }
+    Ok(output)
+}
+
+/// Escapes the characters that would break a TSV row.
+fn tsv_cell(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('\t', "\\t").replace('\n', "\\n").replace('\r', "\\r")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Serialize)]
+    struct Row {
+        path: String,
+        tags: Vec<String>,
+    }
+
+    impl Tabular for Row {
+        const COLUMNS: &'static [&'static str] = &["path", "tags"];
+
+        fn cells(&self) -> Vec<String> {
+            vec![self.path.clone(), self.tags.join(",")]
+        }
+    }
+
+    fn rows() -> Vec<Row> {
+        vec![
+            Row { path: "/a".to_string(), tags: vec!["x".to_string(), "y".to_string()] },
+            Row { path: "/b\tc".to_string(), tags: Vec::new() },
+        ]
+    }
+
+    #[test]
+    fn test_render_json_and_ndjson() {
+        assert_eq!(
+            render_rows(OutputFormat::Json, &rows()).unwrap(),
+            "[{\"path\":\"/a\",\"tags\":[\"x\",\"y\"]},{\"path\":\"/b\\tc\",\"tags\":[]}]\n"
+        );
+        assert_eq!(
+            render_rows(OutputFormat::Ndjson, &rows()).unwrap(),
+            "{\"path\":\"/a\",\"tags\":[\"x\",\"y\"]}\n{\"path\":\"/b\\tc\",\"tags\":[]}\n"
+        );
+        assert_eq!(render_rows::<Row>(OutputFormat::Ndjson, &[]).unwrap(), "");
+    }
+
+    #[test]
+    fn test_render_tsv_escapes_cells() {
+        assert_eq!(
+            render_rows(OutputFormat::Tsv, &rows()).unwrap(),
+            "path\ttags\n/a\tx,y\n/b\\tc\t\n"
+        );
+    }
+}
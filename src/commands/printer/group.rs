@@ -0,0 +1,18 @@
+/// Prints a titled group of `(path, detail)` rows with the details aligned.
+/// Empty groups are not printed.
+pub fn print_group(title: &str, mut entries: Vec<(String, String)>) {
+    if entries.is_empty() {
+        return;
+    }
+    entries.sort_by(|a, b| a.0.cmp(&b.0));
+    println!("{} ({})", title, entries.len());
+    let max_path = entries.iter().map(|(p, _)| p.len()).max().unwrap_or(0);
+    for (path, detail) in entries {
+        if detail.is_empty() {
+            println!("  {}", path);
+        } else {
+            println!("  {:width$}  {}", path, detail, width = max_path);
+        }
+    }
+    println!();
+}
@@ -2,14 +2,18 @@ use log::error;
 
 use crate::commands::printer::error::handle_inquire_error;
 use crate::context::Context;
+use crate::context::database::Database;
+use crate::context::database::models::Repo;
 use crate::helpers::colors::Colorize;
 use crate::helpers::path::remove_dir_with_empty_parents;
+use anyhow::{Context as _, bail};
 use inquire::Confirm;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 pub fn run(c: &mut Context, path: PathBuf, skip_confirmation: bool) {
-    let path_str = path.to_string_lossy();
-
+    // Index records hold resolved paths, so `./x/../api` must be resolved to
+    // stop at the base dir of the repository and drop its record.
+    let path = std::fs::canonicalize(&path).unwrap_or(path);
     // If not skipping confirmation, prompt the user
     if !skip_confirmation {
         let ans = Confirm::new("You're removing a repo from disk, continue?")
@@ -33,24 +37,67 @@ pub fn run(c: &mut Context, path: PathBuf, skip_confirmation: bool) {
         }
     }
 
+    if let Err(e) = remove_repo(c, &path) {
+        eprintln!("{}", e.to_string().red());
+        return;
+    }
+    println!("Repository removed: {:?}", path);
+    println!("{}", "Done!".green());
+}
+
+/// Deletes the repository at `path` from disk, together with parent
+/// directories left empty below its base dir, and drops it from the index.
+pub fn remove_repo(c: &Context, path: &Path) -> anyhow::Result<()> {
+    let path_str = path.to_string_lossy();
+
     // Get the base_dir from the database to use as stop_at parameter
-    let base_dir_path = if let Some(repo) = c.database().get_by_path(&path_str) {
-        let base_dir = repo.base_dir;
-        Some(PathBuf::from(base_dir))
-    } else {
-        None
-    };
+    let base_dir_path =
+        c.database().get_by_path(&path_str).map(|repo| PathBuf::from(repo.base_dir));
 
     // Remove the directory and its empty parents, stopping at the base_dir
-    remove_dir_with_empty_parents(&path, base_dir_path.as_ref())
-        .expect("Error when removing directory");
-    c.database_mut().remove(&path_str);
-
-    println!("Repository removed: {:?}", path);
+    remove_dir_with_empty_parents(&path.to_path_buf(), base_dir_path.as_ref())
+        .with_context(|| format!("Error when removing directory {}", path.display()))?;
 
-    if let Err(e) = c.database_mut().save() {
+    if let Err(e) = c.database_mut().update_with_history("remove", |db| db.remove(&path_str)) {
         error!("Failed to save database: {}", e);
-    } else {
-        println!("{}", "Done!".green());
+    }
+    Ok(())
+}
+
+/// Like [`remove_repo`], but refuses anything that is not the root of a
+/// tracked repository, such as the host and owner directories `find` offers.
+pub fn remove_tracked_repo(c: &Context, path: &Path) -> anyhow::Result<()> {
+    tracked_repo(&c.database(), &path.to_string_lossy())?;
+    remove_repo(c, path)
+}
+
+/// The index record of the repository rooted at `path`.
+pub fn tracked_repo(db: &Database, path: &str) -> anyhow::Result<Repo> {
+    match db.get_by_path(path) {
+        Some(repo) => Ok(repo),
+        None => bail!("Not a tracked repository: {}", path),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_only_tracked_repositories_can_be_removed() {
+        let mut db = Database::in_memory();
+        db.record_item(
+            "/base",
+            "https://github.com/acme/api.git",
+            "github.com",
+            "api",
+            "acme",
+            "/base/github.com/acme/api",
+        );
+
+        assert!(tracked_repo(&db, "/base/github.com/acme/api").is_ok());
+        assert!(tracked_repo(&db, "/base/github.com/acme").is_err());
+        assert!(tracked_repo(&db, "/base/github.com").is_err());
+        assert!(tracked_repo(&db, "/base/github.com/acme/api/src").is_err());
     }
 }
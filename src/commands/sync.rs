@@ -1,6 +1,107 @@
+use super::printer::format::{OutputFormat, Tabular, print_rows};
 use crate::context::Context;
+use crate::helpers::colors::Colorize;
 use crate::internal;
+use crate::internal::sync::SyncSummary;
+use serde::Serialize;
 
-pub fn run(c: &Context) {
-    internal::sync::sync(c, false);
+/// One change of a sync, as printed by `sync --format ndjson|tsv`.
+#[derive(Serialize, Debug, PartialEq, Eq)]
+struct SyncChange<'a> {
+    change: &'static str,
+    path: &'a str,
+    /// Previous path of a moved repository, or previous url of a changed remote.
+    from: Option<&'a str>,
+    /// New url of a changed remote.
+    to: Option<&'a str>,
+}
+
+impl Tabular for SyncChange<'_> {
+    const COLUMNS: &'static [&'static str] = &["change", "path", "from", "to"];
+
+    fn cells(&self) -> Vec<String> {
+        vec![
+            self.change.to_string(),
+            self.path.to_string(),
+            self.from.unwrap_or_default().to_string(),
+            self.to.unwrap_or_default().to_string(),
+        ]
+    }
+}
+
+fn changes(summary: &SyncSummary) -> Vec<SyncChange<'_>> {
+    let mut changes = Vec::new();
+    for path in &summary.added {
+        changes.push(SyncChange { change: "added", path, from: None, to: None });
+    }
+    for path in &summary.removed {
+        changes.push(SyncChange { change: "removed", path, from: None, to: None });
+    }
+    for moved in &summary.moved {
+        changes.push(SyncChange {
+            change: "moved",
+            path: &moved.to,
+            from: Some(&moved.from),
+            to: None,
+        });
+    }
+    for changed in &summary.remote_changed {
+        changes.push(SyncChange {
+            change: "remote_changed",
+            path: &changed.path,
+            from: Some(&changed.from),
+            to: Some(&changed.to),
+        });
+    }
+    changes
+}
+
+pub fn run(c: &Context, format: Option<OutputFormat>) {
+    let Some(format) = format else {
+        internal::sync::sync(c, false);
+        return;
+    };
+
+    let summary = internal::sync::sync(c, true);
+    match format {
+        OutputFormat::Json => match serde_json::to_string(&summary) {
+            Ok(s) => println!("{}", s),
+            Err(e) => eprintln!("{}", format!("Failed to serialize output: {}", e).red()),
+        },
+        OutputFormat::Ndjson | OutputFormat::Tsv => print_rows(format, &changes(&summary)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::internal::sync::MovedRepo;
+
+    #[test]
+    fn test_changes_lists_every_kind() {
+        let summary = SyncSummary {
+            total: 3,
+            added: vec!["/base/new".to_string()],
+            removed: vec!["/base/gone".to_string()],
+            moved: vec![MovedRepo {
+                from: "/base/old".to_string(),
+                to: "/base/renamed".to_string(),
+            }],
+            remote_changed: Vec::new(),
+        };
+
+        assert_eq!(
+            changes(&summary),
+            vec![
+                SyncChange { change: "added", path: "/base/new", from: None, to: None },
+                SyncChange { change: "removed", path: "/base/gone", from: None, to: None },
+                SyncChange {
+                    change: "moved",
+                    path: "/base/renamed",
+                    from: Some("/base/old"),
+                    to: None
+                },
+            ]
+        );
+    }
 }
@@ -0,0 +1,73 @@
+use crate::commands::find::select_repo;
+use crate::context::Context;
+use crate::context::database::models::parse_tags;
+use crate::helpers::colors::Colorize;
+use clap::{Args, Subcommand};
+
+#[derive(Debug, Args)]
+#[command(args_conflicts_with_subcommands = true)]
+pub struct TagArgs {
+    #[command(subcommand)]
+    pub command: Option<TagCommands>,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum TagCommands {
+    #[command(about = "Add tags to a repository")]
+    Add {
+        #[arg(help = "Repository path or keyword")]
+        repo: String,
+        #[arg(help = "Comma separated tags, e.g. work,infra")]
+        tags: String,
+    },
+    #[command(about = "Remove tags from a repository")]
+    Rm {
+        #[arg(help = "Repository path or keyword")]
+        repo: String,
+        #[arg(help = "Comma separated tags, e.g. work,infra")]
+        tags: String,
+    },
+}
+
+pub fn run(c: &mut Context, tag: &TagCommands) {
+    let (keyword, tags, adding) = match tag {
+        TagCommands::Add { repo, tags } => (repo, tags, true),
+        TagCommands::Rm { repo, tags } => (repo, tags, false),
+    };
+
+    let tags = parse_tags(tags);
+    if tags.is_empty() {
+        eprintln!("{}", "No tags given.".red());
+        return;
+    }
+
+    let Some(repo) = select_repo(c, keyword) else {
+        eprintln!("{}", format!("No repository found for: {}", keyword).red());
+        return;
+    };
+
+    let result = c.database_mut().update_with_history("tag", |db| {
+        if adding {
+            db.add_tags(&repo.full_path, &tags);
+        } else {
+            db.remove_tags(&repo.full_path, &tags);
+        }
+    });
+    if let Err(e) = result {
+        eprintln!("{}", format!("Failed to save database: {}", e).red());
+        return;
+    }
+
+    let current = c.database().get_by_path(&repo.full_path).map(|r| r.tags).unwrap_or_default();
+    println!("{}", repo.full_path);
+    if current.is_empty() {
+        println!("  {}", "no tags".green());
+    } else {
+        println!("  {}", format_tags(&current).green());
+    }
+}
+
+/// Formats tags the way they are searched for: `#infra #work`.
+pub fn format_tags(tags: &[String]) -> String {
+    tags.iter().map(|tag| format!("#{}", tag)).collect::<Vec<_>>().join(" ")
+}
@@ -1,8 +1,10 @@
+use super::printer::format::{OutputFormat, Tabular, print_rows};
 use crate::context::Context;
 use crate::helpers::colors::Colorize;
 use crate::helpers::path::ensure_dir_exists;
 use crate::helpers::platform;
 use clap::{Args, Subcommand};
+use serde::Serialize;
 use std::fs;
 use std::time::{Duration, SystemTime};
 
@@ -97,11 +99,11 @@ pub fn create(c: &Context) {
     platform::clipboard::copy_path(&path_str);
 }
 
-pub fn run(c: &mut Context, tmp: &TmpCommands) {
+pub fn run(c: &mut Context, tmp: &TmpCommands, format: Option<OutputFormat>) {
     match tmp {
         TmpCommands::Create => create(&c),
         TmpCommands::Clean => cleanoutdate(&c),
-        TmpCommands::List => list_files(&c),
+        TmpCommands::List => list_files(&c, format),
     }
 }
 
@@ -115,44 +117,89 @@ pub fn cleanoutdate(c: &Context) {
     );
 }
 
-pub fn list_files(c: &Context) {
+/// A temporary directory in `tmp list --format` output.
+#[derive(Serialize)]
+struct TmpEntry {
+    path: String,
+    /// RFC 3339 timestamps in local time.
+    created: String,
+    modified: String,
+    /// Not modified for 7 days, so `tmp clean` would remove it.
+    outdated: bool,
+}
+
+impl Tabular for TmpEntry {
+    const COLUMNS: &'static [&'static str] = &["path", "created", "modified", "outdated"];
+
+    fn cells(&self) -> Vec<String> {
+        vec![
+            self.path.clone(),
+            self.created.clone(),
+            self.modified.clone(),
+            self.outdated.to_string(),
+        ]
+    }
+}
+
+pub fn list_files(c: &Context, format: Option<OutputFormat>) {
     let tmp_dir = c.config().tmp_dir();
     let now = SystemTime::now();
     let seven_days_ago = now - Duration::from_secs(7 * 24 * 60 * 60);
 
-    match fs::read_dir(&tmp_dir) {
-        Ok(entries) => {
-            for entry in entries.flatten() {
-                if let Ok(metadata) = entry.metadata() {
-                    let created = metadata.created().unwrap_or(SystemTime::UNIX_EPOCH);
-                    let modified = metadata.modified().unwrap_or(SystemTime::UNIX_EPOCH);
-                    let is_outdated = modified < seven_days_ago;
-                    let outdated_marker = if is_outdated {
-                        String::from("[outdated]").red()
-                    } else {
-                        // 将modified 转换为 n days ago
-                        let duration = now.duration_since(modified).unwrap();
-                        let days = duration.as_secs() / (24 * 60 * 60);
-
-                        if days > 0 {
-                            format!("[{} days ago]", days).green()
-                        } else {
-                            format!("[{} hours ago]", duration.as_secs() / (60 * 60)).green()
-                        }
-                    };
-
-                    println!(
-                        "{} {} Created: {} Modified: {}",
-                        outdated_marker,
-                        entry.path().to_string_lossy(),
-                        chrono::DateTime::<chrono::Local>::from(created)
-                            .format("%Y-%m-%d %H:%M:%S"),
-                        chrono::DateTime::<chrono::Local>::from(modified)
-                            .format("%Y-%m-%d %H:%M:%S")
-                    );
+    let entries = match fs::read_dir(&tmp_dir) {
+        Ok(entries) => entries,
+        Err(e) => {
+            eprintln!("Failed to read directory {}: {}", tmp_dir, e);
+            return;
+        }
+    };
+
+    if let Some(format) = format {
+        let mut rows: Vec<TmpEntry> = entries
+            .flatten()
+            .filter_map(|entry| {
+                let metadata = entry.metadata().ok()?;
+                let created = metadata.created().unwrap_or(SystemTime::UNIX_EPOCH);
+                let modified = metadata.modified().unwrap_or(SystemTime::UNIX_EPOCH);
+                Some(TmpEntry {
+                    path: entry.path().to_string_lossy().to_string(),
+                    created: chrono::DateTime::<chrono::Local>::from(created).to_rfc3339(),
+                    modified: chrono::DateTime::<chrono::Local>::from(modified).to_rfc3339(),
+                    outdated: modified < seven_days_ago,
+                })
+            })
+            .collect();
+        rows.sort_by(|a, b| a.path.cmp(&b.path));
+        print_rows(format, &rows);
+        return;
+    }
+
+    for entry in entries.flatten() {
+        if let Ok(metadata) = entry.metadata() {
+            let created = metadata.created().unwrap_or(SystemTime::UNIX_EPOCH);
+            let modified = metadata.modified().unwrap_or(SystemTime::UNIX_EPOCH);
+            let is_outdated = modified < seven_days_ago;
+            let outdated_marker = if is_outdated {
+                String::from("[outdated]").red()
+            } else {
+                // 将modified 转换为 n days ago
+                let duration = now.duration_since(modified).unwrap();
+                let days = duration.as_secs() / (24 * 60 * 60);
+
+                if days > 0 {
+                    format!("[{} days ago]", days).green()
+                } else {
+                    format!("[{} hours ago]", duration.as_secs() / (60 * 60)).green()
                 }
-            }
+            };
+
+            println!(
+                "{} {} Created: {} Modified: {}",
+                outdated_marker,
+                entry.path().to_string_lossy(),
+                chrono::DateTime::<chrono::Local>::from(created).format("%Y-%m-%d %H:%M:%S"),
+                chrono::DateTime::<chrono::Local>::from(modified).format("%Y-%m-%d %H:%M:%S")
+            );
         }
-        Err(e) => eprintln!("Failed to read directory {}: {}", tmp_dir, e),
     }
 }
@@ -0,0 +1,170 @@
+use crate::commands::find::select_repo;
+use crate::context::Context;
+use crate::context::database::models::Repo;
+use crate::helpers::colors::Colorize;
+use crate::helpers::{git, path, platform};
+use clap::{Args, Subcommand};
+use std::collections::BTreeMap;
+
+#[derive(Debug, Args)]
+#[command(args_conflicts_with_subcommands = true)]
+pub struct WtArgs {
+    #[command(subcommand)]
+    pub command: Option<WtCommands>,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum WtCommands {
+    #[command(about = "Check a branch out into a new worktree next to the repository")]
+    Add {
+        #[arg(help = "Repository path or keyword")]
+        repo: String,
+        #[arg(help = "Branch to check out, created from HEAD when it does not exist")]
+        branch: String,
+    },
+    #[command(about = "List the worktrees of every repository")]
+    List,
+    #[command(about = "Remove worktrees whose directory is gone")]
+    Prune {
+        #[arg(short = 'n', long = "dry-run", help = "Only show what would be removed")]
+        dry_run: bool,
+    },
+}
+
+pub fn run(c: &mut Context, wt: &WtCommands) {
+    match wt {
+        WtCommands::Add { repo, branch } => add(c, repo, branch),
+        WtCommands::List => list(c),
+        WtCommands::Prune { dry_run } => prune(c, *dry_run),
+    }
+}
+
+fn add(c: &Context, keyword: &str, branch: &str) {
+    let Some(repo) = select_repo(c, keyword) else {
+        eprintln!("{}", format!("No repository found for: {}", keyword).red());
+        return;
+    };
+    // A worktree of a worktree belongs to the same main repository.
+    let main = match &repo.worktree_of {
+        Some(main) => c.database().get_by_path(main).unwrap_or(repo),
+        None => repo,
+    };
+
+    let Some(target) = git::worktree_path(&main.full_path, branch) else {
+        eprintln!("{}", format!("Cannot place a worktree next to: {}", main.full_path).red());
+        return;
+    };
+    if target.exists() {
+        eprintln!("{}", format!("Path already exists: {}", target.display()).red());
+        return;
+    }
+
+    let create = !git::ref_names(&main.full_path).iter().any(|name| name == branch);
+    if let Err(e) = git::add_worktree(&main.full_path, &target, branch, create) {
+        eprintln!("{}", format!("Failed to add worktree: {}", e).red());
+        return;
+    }
+
+    let target = target.to_string_lossy().to_string();
+    let result = c.database_mut().update_with_history("wt add", |db| {
+        db.record_item(
+            &main.base_dir,
+            &main.remote_url,
+            &main.host,
+            &main.repo,
+            &main.owner,
+            &target,
+        );
+        db.update_record(&target, |record| {
+            record.remotes = main.remotes.clone();
+            record.worktree_of = Some(main.full_path.clone());
+        });
+    });
+    if let Err(e) = result {
+        eprintln!(
+            "{}",
+            format!(
+                "Created worktree {} but could not record it ({}); `prog sync` will pick it up.",
+                target, e
+            )
+            .red()
+        );
+        return;
+    }
+
+    println!("{}", format!("Created worktree: {}", target).green());
+    platform::clipboard::copy_path(&target);
+}
+
+fn list(c: &Context) {
+    c.auto_sync_silent();
+
+    let mut worktrees: BTreeMap<String, Vec<Repo>> = BTreeMap::new();
+    for repo in c.database().get_all_items() {
+        if let Some(main) = repo.worktree_of.clone() {
+            worktrees.entry(main).or_default().push(repo);
+        }
+    }
+    if worktrees.is_empty() {
+        println!("{}", "No worktrees.".green());
+        return;
+    }
+
+    for (main, mut repos) in worktrees {
+        println!("{}", path::contract_tilde(&main).green());
+        repos.sort_by(|a, b| a.full_path.cmp(&b.full_path));
+        let width =
+            repos.iter().map(|r| path::contract_tilde(&r.full_path).len()).max().unwrap_or(0);
+        for repo in repos {
+            let display_path = path::contract_tilde(&repo.full_path);
+            let branch = git::get_branch(&repo.full_path);
+            if branch.is_empty() {
+                println!("  {}", display_path);
+            } else {
+                println!("  {:width$}  {}", display_path, format!("[{}]", branch).blue());
+            }
+        }
+    }
+}
+
+fn prune(c: &Context, dry_run: bool) {
+    let repos = c.database().get_all_items();
+    let mains: Vec<&Repo> = repos
+        .iter()
+        .filter(|repo| repo.worktree_of.is_none() && git::has_worktrees(&repo.full_path))
+        .collect();
+    for repo in mains {
+        match git::prune_worktrees(&repo.full_path, dry_run) {
+            Ok(report) => {
+                for line in report {
+                    println!("{}: {}", repo.full_path, line);
+                }
+            }
+            Err(e) => eprintln!("{}", format!("{}: {}", repo.full_path, e).red()),
+        }
+    }
+
+    // Also drop the index records of the worktrees that are gone.
+    let stale: Vec<String> = repos
+        .iter()
+        .filter(|repo| repo.worktree_of.is_some() && !path::exists(&repo.full_path))
+        .map(|repo| repo.full_path.clone())
+        .collect();
+    for path in &stale {
+        println!("{} {}", if dry_run { "Would forget" } else { "Forget" }, path);
+    }
+    if dry_run || stale.is_empty() {
+        return;
+    }
+
+    let result = c.database_mut().update_with_history("wt prune", |db| {
+        for path in &stale {
+            db.remove(path);
+        }
+    });
+    if let Err(e) = result {
+        eprintln!("{}", format!("Failed to save database: {}", e).red());
+        return;
+    }
+    println!("{}", format!("Forgot {} stale worktrees", stale.len()).green());
+}
@@ -12,4 +12,9 @@ auto_sync_interval_secs = 3600
 "#;
 
 pub const DATABASE_FILE: &str = "data.toml";
+pub const DATABASE_LOCK_FILE: &str = "data.lock";
+pub const HISTORY_FOLDER: &str = "history";
+/// Number of index snapshots kept in the history folder.
+pub const HISTORY_LIMIT: usize = 20;
 pub const CONFIG_TOML_FILE: &str = "config.toml";
+pub const DAEMON_SOCKET_FILE: &str = "daemon.sock";
@@ -1,8 +1,11 @@
 use crate::helpers::{
+    exclude::Excludes,
+    layout,
     path::{PROGRAM, expand_tilde},
     rand::get_random_string,
 };
-use log::info;
+use globset::GlobBuilder;
+use log::{info, warn};
 use serde::Deserialize;
 use std::{collections::HashMap, path::PathBuf};
 
@@ -10,6 +13,24 @@ fn default_auto_sync_interval_secs() -> i64 {
     3600
 }
 
+fn default_preferred_remotes() -> Vec<String> {
+    vec!["origin".to_string(), "upstream".to_string()]
+}
+
+fn default_layout() -> String {
+    layout::DEFAULT_LAYOUT.to_string()
+}
+
+/// Routes newly added repositories to a base directory.
+///
+/// `pattern` is a glob matched (case-insensitively) against `host/owner/repo`,
+/// e.g. `github.com/my-company/*`.
+#[derive(Deserialize, Debug, Clone)]
+pub struct BaseRule {
+    pub pattern: String,
+    pub base: String,
+}
+
 #[derive(Deserialize, Debug, Clone)]
 pub struct Config {
     #[serde(default)]
@@ -20,6 +41,32 @@ pub struct Config {
     pub tmp_dir: String,
     #[serde(default = "default_auto_sync_interval_secs")]
     pub auto_sync_interval_secs: i64,
+    #[serde(default)]
+    pub rules: Vec<BaseRule>,
+    /// Let frecency outrank the match kind when ordering `find` results.
+    #[serde(default)]
+    pub rank_by_frecency: bool,
+    /// Remote names, most preferred first, whose URL decides where a
+    /// repository is laid out. Other remotes are used when none of these exist.
+    #[serde(default = "default_preferred_remotes")]
+    pub preferred_remotes: Vec<String>,
+    /// Where repositories go under a base dir, built from `{host}`, `{owner}`
+    /// and `{repo}`.
+    #[serde(default = "default_layout")]
+    pub layout: String,
+    /// How many directory levels below a base dir `sync` searches for
+    /// repositories. Derived from `layout` when unset.
+    #[serde(default)]
+    pub discovery_depth: Option<usize>,
+    /// Globs of repository paths that are not indexed, e.g. `**/node_modules/**`.
+    #[serde(default)]
+    pub exclude: Vec<String>,
+    /// Hosts whose repositories are not indexed, as globs.
+    #[serde(default)]
+    pub exclude_hosts: Vec<String>,
+    /// Owners whose repositories are not indexed, as globs.
+    #[serde(default)]
+    pub exclude_owners: Vec<String>,
 }
 
 impl Config {
@@ -33,6 +80,28 @@ impl Config {
         base_dirs
     }
 
+    /// Returns the expanded base dir of the first rule matching `host/owner/repo`.
+    pub fn route_base_dir(&self, host: &str, owner: &str, repo: &str) -> Option<String> {
+        let target = format!("{}/{}/{}", host, owner, repo);
+
+        for rule in &self.rules {
+            let glob = match GlobBuilder::new(&rule.pattern).case_insensitive(true).build() {
+                Ok(glob) => glob.compile_matcher(),
+                Err(e) => {
+                    warn!("Ignoring invalid base rule pattern '{}': {}", rule.pattern, e);
+                    continue;
+                }
+            };
+
+            if glob.is_match(&target) {
+                info!("Base rule matched: {} -> {}", rule.pattern, rule.base);
+                return Some(expand_tilde(&rule.base));
+            }
+        }
+
+        None
+    }
+
     pub fn replace_alias(&self, url: String) -> String {
         for (key, value) in &self.alias {
             if url.starts_with(key) {
@@ -59,7 +128,136 @@ impl Config {
         path_buf
     }
 
+    /// Path of a repository cloned into `base_dir`, following `layout`.
+    pub fn repo_path(&self, base_dir: &str, host: &str, owner: &str, repo: &str) -> PathBuf {
+        PathBuf::from(base_dir).join(layout::render(&self.layout, host, owner, repo))
+    }
+
+    pub fn discovery_depth(&self) -> usize {
+        self.discovery_depth.unwrap_or_else(|| layout::depth(&self.layout))
+    }
+
+    pub fn excludes(&self) -> Excludes {
+        Excludes::new(&self.exclude, &self.exclude_hosts, &self.exclude_owners)
+    }
+
     pub fn get_auto_sync_interval_secs(&self) -> i64 {
         self.auto_sync_interval_secs
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config_with_rules(rules: &[(&str, &str)]) -> Config {
+        Config {
+            base: vec!["/work".to_string(), "/personal".to_string()],
+            alias: HashMap::new(),
+            tmp_dir: String::new(),
+            auto_sync_interval_secs: default_auto_sync_interval_secs(),
+            rank_by_frecency: false,
+            preferred_remotes: default_preferred_remotes(),
+            layout: default_layout(),
+            discovery_depth: None,
+            exclude: Vec::new(),
+            exclude_hosts: Vec::new(),
+            exclude_owners: Vec::new(),
+            rules: rules
+                .iter()
+                .map(|(pattern, base)| BaseRule {
+                    pattern: pattern.to_string(),
+                    base: base.to_string(),
+                })
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn test_route_base_dir_matches_owner_glob() {
+        let config = config_with_rules(&[("github.com/my-company/*", "/work")]);
+
+        assert_eq!(
+            config.route_base_dir("github.com", "my-company", "api"),
+            Some("/work".to_string())
+        );
+        assert_eq!(config.route_base_dir("github.com", "someone-else", "api"), None);
+    }
+
+    #[test]
+    fn test_route_base_dir_first_rule_wins() {
+        let config = config_with_rules(&[
+            ("gitlab.com/**", "/work"),
+            ("*/bytemain/*", "/personal"),
+            ("github.com/*", "/work"),
+        ]);
+
+        assert_eq!(
+            config.route_base_dir("github.com", "bytemain", "prog"),
+            Some("/personal".to_string())
+        );
+        assert_eq!(
+            config.route_base_dir("github.com", "microsoft", "vscode"),
+            Some("/work".to_string())
+        );
+        assert_eq!(
+            config.route_base_dir("gitlab.com", "group", "project"),
+            Some("/work".to_string())
+        );
+    }
+
+    #[test]
+    fn test_route_base_dir_is_case_insensitive() {
+        let config = config_with_rules(&[("github.com/My-Company/*", "/work")]);
+
+        assert_eq!(
+            config.route_base_dir("GitHub.com", "my-company", "API"),
+            Some("/work".to_string())
+        );
+    }
+
+    #[test]
+    fn test_route_base_dir_skips_invalid_patterns() {
+        let config = config_with_rules(&[("github.com/[", "/personal"), ("github.com/*", "/work")]);
+
+        assert_eq!(config.route_base_dir("github.com", "a", "b"), Some("/work".to_string()));
+    }
+
+    #[test]
+    fn test_rules_deserialize_from_toml() {
+        let config: Config = toml::from_str(
+            r#"
+base = ["~/work", "~/personal"]
+
+[[rules]]
+pattern = "github.com/my-company/*"
+base = "~/work"
+"#,
+        )
+        .unwrap();
+
+        assert_eq!(config.rules.len(), 1);
+        assert_eq!(config.rules[0].pattern, "github.com/my-company/*");
+        assert_eq!(config.rules[0].base, "~/work");
+        assert_eq!(config.layout, layout::DEFAULT_LAYOUT);
+    }
+
+    #[test]
+    fn test_layout_and_discovery_depth() {
+        let mut config = config_with_rules(&[]);
+        assert_eq!(
+            config.repo_path("/work", "github.com", "acme", "api"),
+            PathBuf::from("/work/github.com/acme/api")
+        );
+
+        config.layout = "{repo}".to_string();
+        assert_eq!(
+            config.repo_path("/work", "github.com", "acme", "api"),
+            PathBuf::from("/work/api")
+        );
+        assert_eq!(config.discovery_depth(), 1);
+
+        config.discovery_depth = Some(4);
+        assert_eq!(config.discovery_depth(), 4);
+    }
+}
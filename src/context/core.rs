@@ -1,11 +1,16 @@
 use crate::constants;
 use crate::context::configuration;
 use crate::context::database;
+use crate::context::database::models::Repo;
 use crate::helpers::colors::Colorize;
-use crate::helpers::path::get_config_path;
+use crate::helpers::exclude::Excludes;
+use crate::helpers::layout;
+use crate::helpers::path::{expand_tilde, get_config_path};
+use crate::internal::daemon;
 use crate::internal::sync::check_auto_sync;
 use crate::internal::sync::sync;
 use anyhow::bail;
+use inquire::Select;
 use log::debug;
 use std::cell::LazyCell;
 use std::cell::OnceCell;
@@ -17,6 +22,7 @@ use std::process::exit;
 
 pub struct Context {
     pub config: OnceCell<configuration::Config>,
+    excludes: OnceCell<Excludes>,
     db: RefCell<database::Database>,
     config_file_path: LazyCell<PathBuf>,
 }
@@ -29,7 +35,7 @@ impl Context {
             LazyCell::new(|| get_config_path(constants::CONFIG_TOML_FILE));
         let config: OnceCell<configuration::Config> = OnceCell::new();
 
-        let ctx: Context = Self { config, db, config_file_path };
+        let ctx: Context = Self { config, excludes: OnceCell::new(), db, config_file_path };
 
         if ctx.database().size() == 0 {
             ctx.sync_silent();
@@ -48,17 +54,59 @@ impl Context {
         self.db.borrow_mut()
     }
 
-    pub fn get_base_dir(&self) -> anyhow::Result<String> {
+    /// Picks the base dir a new repository should be cloned into.
+    ///
+    /// An explicit `--base` wins, then the first matching routing rule from the
+    /// config. With several base dirs and no matching rule the user is asked.
+    pub fn get_base_dir(
+        &self,
+        explicit: Option<&str>,
+        host: &str,
+        owner: &str,
+        repo: &str,
+    ) -> anyhow::Result<String> {
         let base_dirs = self.config().base_dirs();
         if base_dirs.is_empty() {
             bail!("Please configure base dir in : {}", self.config_file_path.display());
         }
 
+        let find_configured = |dir: &str| {
+            let wanted = normalize_dir(dir);
+            base_dirs.iter().find(|base_dir| normalize_dir(base_dir) == wanted).cloned()
+        };
+
+        if let Some(explicit) = explicit {
+            let dir = expand_tilde(explicit);
+            return match find_configured(&dir) {
+                Some(base_dir) => Ok(base_dir),
+                None => bail!(
+                    "{} is not a configured base dir, add it to: {}",
+                    dir,
+                    self.config_file_path.display()
+                ),
+            };
+        }
+
+        if let Some(dir) = self.config().route_base_dir(host, owner, repo) {
+            return match find_configured(&dir) {
+                Some(base_dir) => Ok(base_dir),
+                None => bail!(
+                    "Base rule points to {} which is not listed in `base` of: {}",
+                    dir,
+                    self.config_file_path.display()
+                ),
+            };
+        }
+
         if base_dirs.len() == 1 {
             return anyhow::Ok(base_dirs.first().unwrap().clone());
         }
 
-        bail!("Not implemented multiple base dir yet");
+        let message = format!("Which base dir should {}/{}/{} be cloned into?", host, owner, repo);
+        match Select::new(&message, base_dirs).prompt() {
+            Ok(base_dir) => Ok(base_dir),
+            Err(e) => bail!("No base dir selected: {}", e),
+        }
     }
 
     fn init_config(&self) -> anyhow::Result<()> {
@@ -119,16 +167,52 @@ impl Context {
                 );
                 exit(1);
             }
+            if let Err(e) = layout::validate(&config.layout) {
+                eprintln!("{}", format!("{}, in: {}", e, config_file_path.display()).red());
+                exit(1);
+            }
 
             config
         })
     }
 
+    pub fn excludes(&self) -> &Excludes {
+        self.excludes.get_or_init(|| self.config().excludes())
+    }
+
+    /// Whether `repo` is left out by the exclude rules, which may have changed
+    /// since it was indexed.
+    pub fn is_excluded(&self, repo: &Repo) -> bool {
+        self.excludes().excludes(&repo.full_path, &repo.host, &repo.owner)
+    }
+
     pub fn sync_silent(&self) {
-        sync(self, true);
+        if !self.sync_through_daemon(daemon::Request::Sync) {
+            sync(self, true);
+        }
     }
 
     pub fn auto_sync_silent(&self) {
-        check_auto_sync(self);
+        if !self.sync_through_daemon(daemon::Request::Flush) {
+            check_auto_sync(self);
+        }
+    }
+
+    /// Lets a running daemon bring the index up to date and reads it again.
+    /// Returns `false` when no daemon is running.
+    fn sync_through_daemon(&self, request: daemon::Request) -> bool {
+        match daemon::request(&request) {
+            Some(daemon::Response::Synced { .. }) => {
+                self.database_mut().reload();
+                true
+            }
+            _ => false,
+        }
     }
 }
+
+/// Strips trailing separators so `~/work/` and `~/work` compare equal.
+fn normalize_dir(dir: &str) -> &str {
+    let trimmed = dir.trim_end_matches(['/', '\\']);
+    if trimmed.is_empty() { dir } else { trimmed }
+}
@@ -1,13 +1,21 @@
+use super::history::{self, Snapshot};
 use super::index_records::*;
+use super::lock::DbLock;
+use super::migrations::{self, CURRENT_VERSION};
 use super::models::*;
+use super::query::Query;
 use crate::constants;
+use crate::helpers::colors::Colorize;
+use crate::helpers::fuzzy::fuzzy_match;
 use crate::helpers::path::ensure_dir_exists;
 use crate::helpers::path::get_config_path;
-use log::error;
+use log::{error, info, warn};
 use serde::{Deserialize, Serialize};
-use std::fs::File;
+use std::cmp::Ordering;
+use std::fs::{self, File};
 use std::io::{Read, Write};
 use std::path::{Path, PathBuf};
+use std::process::exit;
 use strsim::levenshtein;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -20,10 +28,15 @@ pub enum MatchKind {
     OwnerContains,
     RemoteContains,
     RepoFuzzy,
+    /// The keyword's characters appear in order in `owner/repo`.
+    Subsequence,
+    Tag,
+    /// Matched by `field:value` qualifiers only.
+    Filter,
 }
 
 impl MatchKind {
-    fn rank(&self) -> u8 {
+    pub(crate) fn rank(&self) -> u8 {
         match self {
             MatchKind::RepoExact => 0,
             MatchKind::FullNameExact => 1,
@@ -33,6 +46,26 @@ impl MatchKind {
             MatchKind::OwnerContains => 5,
             MatchKind::RemoteContains => 6,
             MatchKind::RepoFuzzy => 7,
+            MatchKind::Subsequence => 8,
+            MatchKind::Tag => 9,
+            MatchKind::Filter => 10,
+        }
+    }
+
+    /// Stable name used in machine-readable output.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            MatchKind::RepoExact => "repo_exact",
+            MatchKind::FullNameExact => "full_name_exact",
+            MatchKind::RepoContains => "repo_contains",
+            MatchKind::PathContains => "path_contains",
+            MatchKind::OwnerExact => "owner_exact",
+            MatchKind::OwnerContains => "owner_contains",
+            MatchKind::RemoteContains => "remote_contains",
+            MatchKind::RepoFuzzy => "repo_fuzzy",
+            MatchKind::Subsequence => "subsequence",
+            MatchKind::Tag => "tag",
+            MatchKind::Filter => "filter",
         }
     }
 }
@@ -41,30 +74,36 @@ impl MatchKind {
 pub struct MatchedRepo {
     pub repo: Repo,
     pub match_kind: MatchKind,
+    /// Sum of the match kind ranks of every keyword, lower is better.
+    pub rank: u32,
+    /// Subsequence score of the keywords against `owner/repo`, higher is better.
+    pub score: i64,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct Data {
-    version: String,
+    version: u32,
     records: IndexedRecords,
     last_sync_time: Option<chrono::NaiveDateTime>,
+    /// What the user picked for ambiguous keywords.
+    choices: Vec<LearnedChoice>,
 }
 
-const CURRENT_VERSION: &str = "1.0";
-
 impl Data {
     pub fn new() -> Self {
         Self {
-            version: CURRENT_VERSION.to_string(),
+            version: CURRENT_VERSION,
             records: IndexedRecords::new(),
             last_sync_time: None,
+            choices: Vec::new(),
         }
     }
 
     pub fn reset(&mut self) {
-        self.version = CURRENT_VERSION.to_string();
+        self.version = CURRENT_VERSION;
         self.records = IndexedRecords::new();
         self.last_sync_time = None;
+        self.choices = Vec::new();
     }
 
     pub fn record_item(
@@ -77,16 +116,21 @@ impl Data {
         full_path: &str,
     ) {
         let now = chrono::Utc::now().naive_utc();
-        // Get the original creation time if available
-        let created_at = if let Some(existing) = self.records.get(full_path) {
-            existing.created_at
-        } else {
-            now
-        };
 
-        // Create updated record
-        let updated_record = Repo {
-            created_at,
+        // Update an existing record in place so anything else attached to it
+        // (creation time, user metadata) is kept.
+        if let Some(existing) = self.records.get_mut(full_path) {
+            existing.updated_at = now;
+            existing.host = host.to_string();
+            existing.repo = repo.to_string();
+            existing.owner = owner.to_string();
+            existing.base_dir = base_dir.to_string();
+            existing.remote_url = remote_url.to_string();
+            return;
+        }
+
+        let record = Repo {
+            created_at: now,
             updated_at: now,
             host: host.to_string(),
             repo: repo.to_string(),
@@ -94,49 +138,207 @@ impl Data {
             base_dir: base_dir.to_string(),
             remote_url: remote_url.to_string(),
             full_path: full_path.to_string(),
+            ..Default::default()
+        };
+        self.records.insert(full_path, record);
+    }
+
+    /// Re-keys a record under a new path, keeping everything attached to it.
+    pub fn move_record(&mut self, from: &str, to: &str, base_dir: &str) -> bool {
+        match self.records.take(from) {
+            Some(mut record) => {
+                record.full_path = to.to_string();
+                record.base_dir = base_dir.to_string();
+                record.updated_at = chrono::Utc::now().naive_utc();
+                self.records.insert(to, record);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Counts a visit to the repository containing `path`, which may be the
+    /// repository root or any directory inside it.
+    pub fn record_visit(&mut self, path: &str) -> bool {
+        let trimmed = path.trim_end_matches(['/', '\\']);
+        let full_path = Path::new(trimmed)
+            .ancestors()
+            .map(|ancestor| ancestor.to_string_lossy().to_string())
+            .find(|ancestor| self.records.get(ancestor).is_some());
+
+        match full_path.and_then(|full_path| self.records.get_mut(&full_path)) {
+            Some(record) => {
+                record.visit_count = record.visit_count.saturating_add(1);
+                record.last_visited_at = Some(chrono::Utc::now().naive_utc());
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Applies `f` to the record at `path`, returning false if there is none.
+    pub fn update_record<F>(&mut self, path: &str, f: F) -> bool
+    where
+        F: FnOnce(&mut Repo),
+    {
+        match self.records.get_mut(path) {
+            Some(record) => {
+                f(record);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Adds tags to a record, returning false if there is no record at `path`.
+    pub fn add_tags(&mut self, path: &str, tags: &[String]) -> bool {
+        let Some(record) = self.records.get_mut(path) else {
+            return false;
+        };
+        for tag in tags {
+            if !record.tags.contains(tag) {
+                record.tags.push(tag.clone());
+            }
+        }
+        record.tags.sort();
+        true
+    }
+
+    /// Removes tags from a record, returning false if there is no record at `path`.
+    pub fn remove_tags(&mut self, path: &str, tags: &[String]) -> bool {
+        let Some(record) = self.records.get_mut(path) else {
+            return false;
+        };
+        record.tags.retain(|tag| !tags.contains(tag));
+        true
+    }
+
+    /// Sets or clears the note of a record, returning false if there is no record at `path`.
+    pub fn set_note(&mut self, path: &str, note: Option<String>) -> bool {
+        let Some(record) = self.records.get_mut(path) else {
+            return false;
         };
-        self.records.insert(full_path, updated_record);
+        record.note = note.filter(|note| !note.trim().is_empty());
+        true
+    }
+
+    /// The path picked the last time `keyword` matched exactly `candidates`.
+    pub fn learned_choice(&self, keyword: &str, candidates: &[String]) -> Option<String> {
+        let keyword = choice_keyword(keyword);
+        let mut candidates = candidates.to_vec();
+        candidates.sort();
+        self.choices
+            .iter()
+            .find(|choice| choice.keyword == keyword && choice.candidates == candidates)
+            .map(|choice| choice.path.clone())
+    }
+
+    /// Remembers that `path` was picked when `keyword` matched `candidates`,
+    /// replacing what was learned for the keyword before.
+    pub fn learn_choice(&mut self, keyword: &str, mut candidates: Vec<String>, path: &str) {
+        let keyword = choice_keyword(keyword);
+        candidates.sort();
+        self.choices.retain(|choice| choice.keyword != keyword);
+        self.choices.push(LearnedChoice {
+            keyword,
+            candidates,
+            path: path.to_string(),
+            chosen_at: chrono::Utc::now().naive_utc(),
+        });
+    }
+
+    /// Forgets what was learned for `keyword`, or for every keyword, and
+    /// returns how many choices were dropped.
+    pub fn forget_choices(&mut self, keyword: Option<&str>) -> usize {
+        let before = self.choices.len();
+        match keyword.map(choice_keyword) {
+            Some(keyword) => self.choices.retain(|choice| choice.keyword != keyword),
+            None => self.choices.clear(),
+        }
+        before - self.choices.len()
     }
 
-    pub fn find(&self, keyword: &str) -> Vec<MatchedRepo> {
-        let keyword = keyword.to_lowercase();
+    /// Finds repositories matching the [`Query`] `query`, best match first.
+    ///
+    /// Every whitespace-separated term must match. Results are ordered by the
+    /// combined match kind of all keywords, with frecency breaking ties. With
+    /// `rank_by_frecency` frecency is compared first instead. Repositories
+    /// matched only as a subsequence are left out when any other matches.
+    pub fn find(&self, query: &str, rank_by_frecency: bool) -> Vec<MatchedRepo> {
+        let query = Query::parse(query);
+        let now = chrono::Utc::now().naive_utc();
 
         // Use iterator to filter records first, then clone only matching records.
         // This is more memory-efficient than get_all_sorted() which clones all records upfront.
         // We still need to clone matching records for the sort operation below.
-        let mut results: Vec<MatchedRepo> = self
+        let mut results: Vec<(MatchedRepo, usize, bool)> = self
             .records
             .iter()
             .filter_map(|repo| {
-                match_kind(repo, &keyword)
-                    .map(|match_kind| MatchedRepo { repo: repo.clone(), match_kind })
+                let kinds = query.keyword_kinds(repo)?;
+                let match_kind = kinds
+                    .iter()
+                    .map(|(_, kind)| *kind)
+                    .max_by_key(MatchKind::rank)
+                    .unwrap_or(MatchKind::Filter);
+                let rank = kinds.iter().map(|(_, kind)| u32::from(kind.rank())).sum();
+                let score = kinds
+                    .iter()
+                    .filter(|(_, kind)| *kind == MatchKind::Subsequence)
+                    .map(|(keyword, _)| subsequence_score(repo, keyword))
+                    .sum();
+                let repo_name = repo.repo.to_lowercase();
+                let distance = kinds
+                    .iter()
+                    .map(|(keyword, _)| levenshtein(&repo_name, keyword))
+                    .min()
+                    .unwrap_or_else(|| repo_name.chars().count());
+                let subsequence = kinds.iter().any(|(_, kind)| *kind == MatchKind::Subsequence);
+                Some((
+                    MatchedRepo { repo: repo.clone(), match_kind, rank, score },
+                    distance,
+                    subsequence,
+                ))
             })
             .collect();
 
-        // Sort results by match priority, then Levenshtein distance (similarity to keyword)
-        results.sort_by(|a, b| {
-            let rank_cmp = a.match_kind.rank().cmp(&b.match_kind.rank());
-            if rank_cmp != std::cmp::Ordering::Equal {
-                return rank_cmp;
-            }
+        // Subsequences are a fallback for typos and abbreviations, and would
+        // otherwise open the chooser next to an exact match.
+        if results.iter().any(|(_, _, subsequence)| !subsequence) {
+            results.retain(|(_, _, subsequence)| !subsequence);
+        }
 
-            let repo_a = a.repo.repo.to_lowercase();
-            let repo_b = b.repo.repo.to_lowercase();
-            let dist_a = levenshtein(&repo_a, &keyword);
-            let dist_b = levenshtein(&repo_b, &keyword);
+        // Sort results by match priority and frecency, then Levenshtein distance (similarity to keyword)
+        results.sort_by(|(a, dist_a, _), (b, dist_b, _)| {
+            let rank_cmp = a.rank.cmp(&b.rank).then(b.score.cmp(&a.score));
+            let frecency_cmp =
+                b.repo.frecency(now).partial_cmp(&a.repo.frecency(now)).unwrap_or(Ordering::Equal);
+            let (first, second) =
+                if rank_by_frecency { (frecency_cmp, rank_cmp) } else { (rank_cmp, frecency_cmp) };
+            if first != Ordering::Equal {
+                return first;
+            }
+            if second != Ordering::Equal {
+                return second;
+            }
 
-            let dist_cmp = dist_a.cmp(&dist_b);
-            if dist_cmp != std::cmp::Ordering::Equal {
+            let dist_cmp = dist_a.cmp(dist_b);
+            if dist_cmp != Ordering::Equal {
                 return dist_cmp;
             }
 
-            repo_a.cmp(&repo_b)
+            a.repo.repo.to_lowercase().cmp(&b.repo.repo.to_lowercase())
         });
 
-        results
+        results.into_iter().map(|(matched, _, _)| matched).collect()
     }
 }
 
+/// Learned choices are keyed case-insensitively, ignoring spacing.
+fn choice_keyword(keyword: &str) -> String {
+    keyword.split_whitespace().collect::<Vec<_>>().join(" ").to_lowercase()
+}
+
 /// Split a string by common separators used in repository names (-, _, .).
 fn split_segments(s: &str) -> impl Iterator<Item = &str> {
     s.split(['-', '_', '.'])
@@ -162,53 +364,174 @@ fn fuzzy_segments_match(target: &str, keyword: &str) -> bool {
     true
 }
 
-fn match_kind(repo: &Repo, keyword: &str) -> Option<MatchKind> {
+/// Subsequence score of `keyword` against `owner/repo`.
+fn subsequence_score(repo: &Repo, keyword: &str) -> i64 {
+    let full_name = format!("{}/{}", repo.owner, repo.repo);
+    fuzzy_match(&full_name, keyword).map_or(0, |m| m.score)
+}
+
+/// Matches a single lowercase keyword against every field of `repo`.
+pub(super) fn match_kind(repo: &Repo, keyword: &str) -> Option<MatchKind> {
+    // `#tag` only matches tags.
+    if let Some(tag) = keyword.strip_prefix('#') {
+        return repo.has_tag(tag).then_some(MatchKind::Tag);
+    }
+
     let repo_name = repo.repo.to_lowercase();
     let owner = repo.owner.to_lowercase();
     let full_path = repo.full_path.to_lowercase();
     let remote_url = repo.remote_url.to_lowercase();
     let full_name = format!("{}/{}", owner, repo_name);
+    // Other remotes (e.g. the upstream of a fork) are matched as well.
+    let remotes = &repo.remotes;
 
     if repo_name == keyword {
         Some(MatchKind::RepoExact)
-    } else if full_name == keyword {
+    } else if full_name == keyword
+        || remotes.iter().any(|r| format!("{}/{}", r.owner, r.repo).to_lowercase() == keyword)
+    {
         Some(MatchKind::FullNameExact)
     } else if repo_name.contains(keyword) {
         Some(MatchKind::RepoContains)
     } else if full_path.contains(keyword) {
         Some(MatchKind::PathContains)
-    } else if owner == keyword {
+    } else if owner == keyword || remotes.iter().any(|r| r.owner.to_lowercase() == keyword) {
         Some(MatchKind::OwnerExact)
     } else if owner.contains(keyword) {
         Some(MatchKind::OwnerContains)
-    } else if remote_url.contains(keyword) {
+    } else if remote_url.contains(keyword)
+        || remotes.iter().any(|r| r.url.to_lowercase().contains(keyword))
+    {
         Some(MatchKind::RemoteContains)
     } else if fuzzy_segments_match(&repo_name, keyword) {
         Some(MatchKind::RepoFuzzy)
+    } else if fuzzy_match(&full_name, keyword).is_some() {
+        Some(MatchKind::Subsequence)
+    } else if repo.has_tag(keyword) {
+        Some(MatchKind::Tag)
     } else {
         None
     }
 }
 
+/// Result of reading the index from disk.
+struct Loaded {
+    data: Data,
+    /// Schema version the file was migrated from, if it was out of date.
+    migrated_from: Option<u32>,
+}
+
+enum LoadError {
+    /// The file was written by a newer prog and must not be touched.
+    Unsupported(String),
+    /// The file is missing, unreadable or corrupted.
+    Invalid(String),
+}
+
+impl std::fmt::Display for LoadError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LoadError::Unsupported(e) | LoadError::Invalid(e) => write!(f, "{}", e),
+        }
+    }
+}
+
 pub struct Database {
     data: Data,
+    path: PathBuf,
 }
 
 impl Database {
     pub fn new() -> Self {
-        let database_file = Self::get_db_path();
-
-        if database_file.exists() {
-            match Self::load_from_file(&database_file) {
-                Ok(db) => db,
-                Err(e) => {
-                    error!("Error loading database: {}. Creating a new one.", e);
-                    Self::create_new_database()
+        Self::open(Self::get_db_path())
+    }
+
+    /// Opens the database stored at `path`, migrating older schemas and
+    /// recovering from a corrupted file with the last-known-good backup.
+    fn open(path: PathBuf) -> Self {
+        let loaded = match DbLock::shared(&Self::lock_path(&path)) {
+            Ok(_guard) if path.exists() => Self::load_from_file(&path),
+            Ok(_guard) => Err(LoadError::Invalid(String::from("Database file does not exist"))),
+            Err(e) => Err(LoadError::Invalid(e)),
+        };
+        match loaded {
+            Ok(Loaded { data, migrated_from: None }) => return Self { data, path },
+            Err(LoadError::Unsupported(e)) => Self::exit_unsupported(&e),
+            _ => {}
+        }
+
+        // Loading failed or the schema needs upgrading; retry under the writer
+        // lock, since another process may have been halfway through replacing
+        // the file, then recover or persist the migrated data.
+        let _guard = match DbLock::exclusive(&Self::lock_path(&path)) {
+            Ok(guard) => Some(guard),
+            Err(e) => {
+                error!("{}", e);
+                None
+            }
+        };
+
+        let data = if path.exists() {
+            match Self::load_from_file(&path) {
+                Ok(Loaded { data, migrated_from: None }) => return Self { data, path },
+                Ok(Loaded { data, migrated_from: Some(version) }) => {
+                    info!("Migrated database from schema v{} to v{}", version, CURRENT_VERSION);
+                    data
+                }
+                Err(LoadError::Unsupported(e)) => Self::exit_unsupported(&e),
+                Err(LoadError::Invalid(e)) => {
+                    error!("Error loading database: {}", e);
+                    Self::recover(&path)
                 }
             }
         } else {
-            Self::create_new_database()
+            Data::new()
+        };
+
+        let db = Self { data, path };
+        if let Err(e) = db.write_to_file() {
+            error!("Failed to save database: {}", e);
+        }
+        db
+    }
+
+    /// Reads the index again, to pick up what another process wrote to it.
+    pub fn reload(&mut self) {
+        *self = Self::open(self.path.clone());
+    }
+
+    /// Creates a database that is never written to disk, for tests.
+    #[cfg(test)]
+    pub(crate) fn in_memory() -> Self {
+        Self { data: Data::new(), path: PathBuf::from("in-memory.toml") }
+    }
+
+    fn exit_unsupported(message: &str) -> ! {
+        eprintln!("{}", message.red());
+        exit(1);
+    }
+
+    /// Moves a corrupted database aside and falls back to the backup copy.
+    fn recover(path: &Path) -> Data {
+        let corrupt_path = Self::sibling_path(path, "corrupt");
+        match fs::rename(path, &corrupt_path) {
+            Ok(_) => error!("Corrupted database kept at {}", corrupt_path.display()),
+            Err(e) => error!("Unable to move corrupted database aside: {}", e),
+        }
+
+        let backup_path = Self::sibling_path(path, "bak");
+        if backup_path.exists() {
+            match Self::load_from_file(&backup_path) {
+                Ok(Loaded { data, .. }) => {
+                    warn!("Restored database from backup {}", backup_path.display());
+                    return data;
+                }
+                Err(e) => error!("Error loading database backup: {}", e),
+            }
         }
+
+        error!("Creating a new database.");
+        Data::new()
     }
 
     pub fn reset(&mut self) {
@@ -221,25 +544,115 @@ impl Database {
         database_path.join(constants::DATABASE_FILE)
     }
 
-    fn load_from_file(path: &Path) -> Result<Self, String> {
-        let mut file =
-            File::open(path).map_err(|e| format!("Unable to open database file: {}", e))?;
+    fn lock_path(path: &Path) -> PathBuf {
+        path.with_file_name(constants::DATABASE_LOCK_FILE)
+    }
+
+    /// Returns `data.toml.<suffix>` next to the database file.
+    fn sibling_path(path: &Path, suffix: &str) -> PathBuf {
+        let mut file_name = path.file_name().unwrap_or_default().to_os_string();
+        file_name.push(".");
+        file_name.push(suffix);
+        path.with_file_name(file_name)
+    }
+
+    /// Reads and deserializes the index, upgrading older schemas in memory.
+    ///
+    /// Before an older schema is migrated, the original file is copied to
+    /// `data.toml.v<version>.bak`.
+    fn load_from_file(path: &Path) -> Result<Loaded, LoadError> {
+        let mut file = File::open(path)
+            .map_err(|e| LoadError::Invalid(format!("Unable to open database file: {}", e)))?;
         let mut contents = String::new();
         file.read_to_string(&mut contents)
-            .map_err(|e| format!("Unable to read database file: {}", e))?;
+            .map_err(|e| LoadError::Invalid(format!("Unable to read database file: {}", e)))?;
+
+        Self::load_from_str(&contents, path, true)
+    }
+
+    /// Deserializes index `contents` read from `path`, migrating older schemas.
+    /// With `backup`, the unmigrated contents are kept next to `path`.
+    fn load_from_str(contents: &str, path: &Path, backup: bool) -> Result<Loaded, LoadError> {
+        let mut table: toml::Table = toml::from_str(contents)
+            .map_err(|e| LoadError::Invalid(format!("Unable to deserialize database: {}", e)))?;
+        let version = migrations::detect_version(&table).map_err(LoadError::Invalid)?;
+
+        if version > CURRENT_VERSION {
+            return Err(LoadError::Unsupported(format!(
+                "Database {} uses schema v{}, but this version of prog only supports up to v{}. Please upgrade prog.",
+                path.display(),
+                version,
+                CURRENT_VERSION
+            )));
+        }
+
+        let migrated_from = if version < CURRENT_VERSION {
+            if backup {
+                let backup_path = Self::sibling_path(path, &format!("v{}.bak", version));
+                fs::write(&backup_path, contents).map_err(|e| {
+                    LoadError::Invalid(format!(
+                        "Unable to back up database before migration: {}",
+                        e
+                    ))
+                })?;
+            }
+            migrations::migrate(&mut table).map_err(LoadError::Invalid)?;
+            Some(version)
+        } else {
+            None
+        };
+
+        let data = Data::deserialize(table)
+            .map_err(|e| LoadError::Invalid(format!("Unable to deserialize database: {}", e)))?;
+        Ok(Loaded { data, migrated_from })
+    }
 
-        toml::from_str(&contents)
-            .map(|data| Self { data })
-            .map_err(|e| format!("Unable to deserialize database: {}", e))
+    /// Reports the schema version of the index on disk and the migrations it
+    /// still needs, without modifying anything.
+    pub fn pending_migrations() -> Result<(u32, Vec<&'static migrations::Migration>), String> {
+        let path = Self::get_db_path();
+        if !path.exists() {
+            return Ok((CURRENT_VERSION, Vec::new()));
+        }
+
+        let _guard = DbLock::shared(&Self::lock_path(&path))?;
+        let contents = fs::read_to_string(&path)
+            .map_err(|e| format!("Unable to read database file: {}", e))?;
+        let table: toml::Table = toml::from_str(&contents)
+            .map_err(|e| format!("Unable to deserialize database: {}", e))?;
+        let version = migrations::detect_version(&table)?;
+        Ok((version, migrations::pending(version)))
     }
 
-    fn save_to_file(&self, path: &Path) -> Result<(), String> {
+    /// Writes the database to a temporary file and renames it over the real one,
+    /// so readers only ever see a complete file. The previous file is kept as
+    /// `data.toml.bak`. Callers must hold the writer lock.
+    fn write_to_file(&self) -> Result<(), String> {
         let contents = toml::to_string(&self.data)
             .map_err(|e| format!("Unable to serialize database: {}", e))?;
-        let mut file =
-            File::create(path).map_err(|e| format!("Unable to create database file: {}", e))?;
+
+        let tmp_path = Self::sibling_path(&self.path, &format!("tmp.{}", std::process::id()));
+        let mut file = File::create(&tmp_path)
+            .map_err(|e| format!("Unable to create database file: {}", e))?;
         file.write_all(contents.as_bytes())
-            .map_err(|e| format!("Unable to write to database file: {}", e))
+            .and_then(|_| file.sync_all())
+            .map_err(|e| format!("Unable to write to database file: {}", e))?;
+        drop(file);
+
+        if self.path.exists() {
+            let backup_path = Self::sibling_path(&self.path, "bak");
+            let _ = fs::remove_file(&backup_path);
+            if fs::hard_link(&self.path, &backup_path).is_err()
+                && let Err(e) = fs::copy(&self.path, &backup_path)
+            {
+                warn!("Unable to back up database: {}", e);
+            }
+        }
+
+        fs::rename(&tmp_path, &self.path).map_err(|e| {
+            let _ = fs::remove_file(&tmp_path);
+            format!("Unable to replace database file: {}", e)
+        })
     }
 
     pub fn get_last_sync_time(&self) -> Option<chrono::NaiveDateTime> {
@@ -250,17 +663,94 @@ impl Database {
         self.data.last_sync_time = Some(chrono::Utc::now().naive_utc());
     }
 
-    fn create_new_database() -> Self {
-        let db = Data::new();
-        let db = Self { data: db };
+    /// Applies `f` and saves the result as one step under the writer lock.
+    ///
+    /// The index is reloaded from disk first, so changes written by another
+    /// process since this one started are not overwritten. An index written by
+    /// a newer prog is left alone, and a corrupted one is recovered from the
+    /// backup the way [`open`](Self::open) does. The file is only rewritten
+    /// when `f` changed something.
+    pub fn update<F>(&mut self, f: F) -> Result<(), String>
+    where
+        F: FnOnce(&mut Self),
+    {
+        let _guard = DbLock::exclusive(&Self::lock_path(&self.path))?;
+        let mut reloaded = false;
+        if self.path.exists() {
+            self.data = match Self::load_from_file(&self.path) {
+                Ok(Loaded { data, migrated_from }) => {
+                    reloaded = migrated_from.is_none();
+                    data
+                }
+                Err(LoadError::Unsupported(e)) => return Err(e),
+                Err(LoadError::Invalid(e)) => {
+                    error!("Error loading database: {}", e);
+                    Self::recover(&self.path)
+                }
+            };
+        }
+        let before = toml::to_string(&self.data).ok();
+        f(self);
+        // Nothing to save when `f` changed nothing, e.g. a visit to an
+        // untracked directory.
+        if reloaded && before.is_some() && before == toml::to_string(&self.data).ok() {
+            return Ok(());
+        }
+        self.write_to_file()
+    }
+
+    /// Like [`update`](Self::update), but when `f` changes any record the
+    /// previous index is kept as a snapshot tagged with `command`, so it can be
+    /// brought back with `prog db restore`.
+    pub fn update_with_history<F>(&mut self, command: &str, f: F) -> Result<(), String>
+    where
+        F: FnOnce(&mut Self),
+    {
+        let mut before = None;
+        self.update(|db| {
+            before = Some((db.records_fingerprint(), toml::to_string(&db.data)));
+            f(db);
+            let Some((fingerprint, Ok(contents))) = before.take() else {
+                return;
+            };
+            if fingerprint != db.records_fingerprint() {
+                let dir = Self::history_dir(&db.path);
+                if let Err(e) = history::save(&dir, command, &contents, constants::HISTORY_LIMIT) {
+                    warn!("Unable to save index snapshot: {}", e);
+                }
+            }
+        })
+    }
+
+    fn records_fingerprint(&self) -> String {
+        serde_json::to_string(&self.data.records).unwrap_or_default()
+    }
 
-        db.save().unwrap();
-        db
+    fn history_dir(path: &Path) -> PathBuf {
+        path.with_file_name(constants::HISTORY_FOLDER)
     }
 
-    pub(crate) fn save(&self) -> Result<(), String> {
-        let database_file = Self::get_db_path();
-        self.save_to_file(&database_file)
+    /// Lists the saved snapshots of the index, newest first.
+    pub fn history() -> Vec<Snapshot> {
+        history::list(&Self::history_dir(&Self::get_db_path()))
+    }
+
+    /// Replaces the index with the snapshot `id` (or a unique prefix of it).
+    /// The index being replaced is itself saved as a snapshot first.
+    pub fn restore(id: &str) -> Result<Snapshot, String> {
+        Self::restore_at(Self::get_db_path(), id)
+    }
+
+    fn restore_at(path: PathBuf, id: &str) -> Result<Snapshot, String> {
+        let snapshot = history::find(&Self::history_dir(&path), id)?;
+        let contents = fs::read_to_string(&snapshot.path)
+            .map_err(|e| format!("Unable to read snapshot {}: {}", snapshot.id, e))?;
+        let Loaded { data, .. } =
+            Self::load_from_str(&contents, &snapshot.path, false).map_err(|e| e.to_string())?;
+
+        let mut db = Self { data: Data::new(), path };
+        db.update_with_history("restore", |db| db.data = data)?;
+        Ok(snapshot)
     }
 
     pub fn record_item(
@@ -274,8 +764,38 @@ impl Database {
     ) {
         self.data.record_item(base_dir, remote_url, host, repo, owner, full_path);
     }
-    pub fn find(&self, keyword: &str) -> Vec<MatchedRepo> {
-        self.data.find(keyword)
+    pub fn find(&self, query: &str, rank_by_frecency: bool) -> Vec<MatchedRepo> {
+        self.data.find(query, rank_by_frecency)
+    }
+    pub fn record_visit(&mut self, path: &str) -> bool {
+        self.data.record_visit(path)
+    }
+    pub fn update_record<F>(&mut self, path: &str, f: F) -> bool
+    where
+        F: FnOnce(&mut Repo),
+    {
+        self.data.update_record(path, f)
+    }
+    pub fn add_tags(&mut self, path: &str, tags: &[String]) -> bool {
+        self.data.add_tags(path, tags)
+    }
+    pub fn remove_tags(&mut self, path: &str, tags: &[String]) -> bool {
+        self.data.remove_tags(path, tags)
+    }
+    pub fn set_note(&mut self, path: &str, note: Option<String>) -> bool {
+        self.data.set_note(path, note)
+    }
+    pub fn move_record(&mut self, from: &str, to: &str, base_dir: &str) -> bool {
+        self.data.move_record(from, to, base_dir)
+    }
+    pub fn learned_choice(&self, keyword: &str, candidates: &[String]) -> Option<String> {
+        self.data.learned_choice(keyword, candidates)
+    }
+    pub fn learn_choice(&mut self, keyword: &str, candidates: Vec<String>, path: &str) {
+        self.data.learn_choice(keyword, candidates, path)
+    }
+    pub fn forget_choices(&mut self, keyword: Option<&str>) -> usize {
+        self.data.forget_choices(keyword)
     }
     pub fn remove(&mut self, path: &str) {
         self.data.records.remove(path);
@@ -343,12 +863,164 @@ mod tests {
         data
     }
 
+    fn record_repo(db: &mut Database, name: &str) {
+        db.record_item(
+            "/base",
+            &format!("https://github.com/user/{}.git", name),
+            "github.com",
+            name,
+            "user",
+            &format!("/base/github.com/user/{}", name),
+        );
+    }
+
+    #[test]
+    fn test_open_creates_missing_database() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let path = temp_dir.path().join("data.toml");
+
+        let db = Database::open(path.clone());
+
+        assert_eq!(db.size(), 0);
+        assert!(path.exists(), "a fresh database should be written to disk");
+    }
+
+    #[test]
+    fn test_update_writes_atomically_and_keeps_backup() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let path = temp_dir.path().join("data.toml");
+
+        let mut db = Database::open(path.clone());
+        db.update(|db| record_repo(db, "prog")).unwrap();
+        db.update(|db| record_repo(db, "vscode")).unwrap();
+
+        let reopened = Database::open(path.clone());
+        assert_eq!(reopened.size(), 2);
+
+        let backup = Database::load_from_file(&temp_dir.path().join("data.toml.bak")).ok().unwrap();
+        assert_eq!(backup.data.records.size(), 1, "backup should hold the previous version");
+
+        let leftovers: Vec<_> = fs::read_dir(temp_dir.path())
+            .unwrap()
+            .flatten()
+            .filter(|entry| entry.file_name().to_string_lossy().contains(".tmp."))
+            .collect();
+        assert!(leftovers.is_empty(), "temporary files should be renamed away");
+    }
+
+    #[test]
+    fn test_update_merges_changes_from_other_processes() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let path = temp_dir.path().join("data.toml");
+
+        let mut first = Database::open(path.clone());
+        let mut second = Database::open(path.clone());
+
+        first.update(|db| record_repo(db, "prog")).unwrap();
+        second.update(|db| record_repo(db, "vscode")).unwrap();
+
+        let reopened = Database::open(path);
+        assert!(reopened.get_by_path("/base/github.com/user/prog").is_some());
+        assert!(reopened.get_by_path("/base/github.com/user/vscode").is_some());
+    }
+
+    #[test]
+    fn test_open_recovers_from_backup_on_corruption() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let path = temp_dir.path().join("data.toml");
+
+        let mut db = Database::open(path.clone());
+        db.update(|db| record_repo(db, "prog")).unwrap();
+        db.update(|db| record_repo(db, "vscode")).unwrap();
+
+        fs::write(&path, "this is [not valid toml").unwrap();
+
+        let recovered = Database::open(path.clone());
+        assert_eq!(recovered.size(), 1, "should fall back to the last-known-good copy");
+        assert!(recovered.get_by_path("/base/github.com/user/prog").is_some());
+
+        let corrupt = temp_dir.path().join("data.toml.corrupt");
+        assert_eq!(fs::read_to_string(corrupt).unwrap(), "this is [not valid toml");
+        assert!(Database::load_from_file(&path).is_ok(), "recovered data should be saved");
+    }
+
+    #[test]
+    fn test_update_skips_writing_when_nothing_changed() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let path = temp_dir.path().join("data.toml");
+        let backup_path = temp_dir.path().join("data.toml.bak");
+
+        let mut db = Database::open(path.clone());
+        db.update(|db| record_repo(db, "prog")).unwrap();
+        fs::remove_file(&backup_path).unwrap();
+
+        db.update(|db| assert!(!db.record_visit("/elsewhere"))).unwrap();
+        assert!(!backup_path.exists(), "an unchanged index should not be rewritten");
+
+        db.update(|db| assert!(db.record_visit("/base/github.com/user/prog"))).unwrap();
+        assert!(backup_path.exists());
+    }
+
+    #[test]
+    fn test_update_leaves_newer_schema_alone() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let path = temp_dir.path().join("data.toml");
+
+        let mut db = Database::open(path.clone());
+        let newer = format!("version = {}\n", CURRENT_VERSION + 1);
+        fs::write(&path, &newer).unwrap();
+
+        assert!(db.update(|db| record_repo(db, "prog")).is_err());
+        assert_eq!(fs::read_to_string(&path).unwrap(), newer);
+    }
+
+    #[test]
+    fn test_update_recovers_from_backup_on_corruption() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let path = temp_dir.path().join("data.toml");
+
+        let mut db = Database::open(path.clone());
+        db.update(|db| record_repo(db, "prog")).unwrap();
+        db.update(|db| record_repo(db, "vscode")).unwrap();
+        fs::write(&path, "this is [not valid toml").unwrap();
+
+        db.update(|db| record_repo(db, "api")).unwrap();
+
+        let reopened = Database::open(path);
+        assert!(reopened.get_by_path("/base/github.com/user/prog").is_some());
+        assert!(reopened.get_by_path("/base/github.com/user/api").is_some());
+        assert!(temp_dir.path().join("data.toml.corrupt").exists());
+    }
+
+    #[test]
+    fn test_update_with_history_snapshots_record_changes() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let path = temp_dir.path().join("data.toml");
+        let history_dir = temp_dir.path().join("history");
+
+        let mut db = Database::open(path.clone());
+        db.update_with_history("add", |db| record_repo(db, "prog")).unwrap();
+        db.update_with_history("sync", |db| db.update_last_sync_time()).unwrap();
+        assert_eq!(history::list(&history_dir).len(), 1, "unchanged records take no snapshot");
+
+        db.update_with_history("clean", |db| db.reset()).unwrap();
+        let snapshots = history::list(&history_dir);
+        assert_eq!(snapshots[0].command, "clean");
+        assert_eq!(snapshots[0].records, 1);
+
+        let restored = Database::restore_at(path.clone(), &snapshots[0].id).unwrap();
+        assert_eq!(restored.command, "clean");
+        let reopened = Database::open(path);
+        assert!(reopened.get_by_path("/base/github.com/user/prog").is_some());
+        assert_eq!(history::list(&history_dir)[0].command, "restore");
+    }
+
     #[test]
     fn test_find_exact_match_first() {
         let data = create_test_data();
 
         // Search for "prog" should return "prog" as exact match first (distance 0)
-        let results = data.find("prog");
+        let results = data.find("prog", false);
 
         assert!(!results.is_empty(), "Should find results");
         assert_eq!(
@@ -365,7 +1037,7 @@ mod tests {
         // - "prog" has distance 0 (exact match)
         // - "prog-cli" has distance 4 (4 insertions: '-', 'c', 'l', 'i')
         // - "my-prog-tools" has distance 9 (prefix 'my-' and suffix '-tools')
-        let results = data.find("prog");
+        let results = data.find("prog", false);
 
         // First result should be exact match
         assert_eq!(results[0].repo.repo, "prog", "Exact match should be first");
@@ -412,7 +1084,7 @@ mod tests {
             "/base/github.com/user/prog",
         );
 
-        let results = data.find("prog");
+        let results = data.find("prog", false);
 
         // "prog" should be first (distance 0)
         assert_eq!(results[0].repo.repo, "prog", "Exact match should be first");
@@ -432,9 +1104,9 @@ mod tests {
         let data = create_test_data();
 
         // Run find multiple times and verify the order is always the same
-        let results1 = data.find("prog");
-        let results2 = data.find("prog");
-        let results3 = data.find("prog");
+        let results1 = data.find("prog", false);
+        let results2 = data.find("prog", false);
+        let results3 = data.find("prog", false);
 
         let order1: Vec<&str> = results1.iter().map(|r| r.repo.repo.as_str()).collect();
         let order2: Vec<&str> = results2.iter().map(|r| r.repo.repo.as_str()).collect();
@@ -465,13 +1137,207 @@ mod tests {
             "/base/pyenv-versions",
         );
 
-        let results = data.find("version-fox");
+        let results = data.find("version-fox", false);
 
         assert_eq!(results[0].repo.repo, "version-fox");
         assert_eq!(results[0].match_kind, MatchKind::RepoExact);
         assert_eq!(results[1].match_kind, MatchKind::OwnerExact);
     }
 
+    fn visit(data: &mut Data, path: &str, times: u32) {
+        for _ in 0..times {
+            assert!(data.record_visit(path));
+        }
+    }
+
+    #[test]
+    fn test_record_visit_resolves_subdirectories() {
+        let mut data = create_test_data();
+
+        assert!(data.record_visit("/base/github.com/user/prog/src/commands"));
+        assert!(data.record_visit("/base/github.com/user/prog/"));
+        assert!(!data.record_visit("/base/github.com/user"));
+
+        let repo = data.records.get("/base/github.com/user/prog").unwrap();
+        assert_eq!(repo.visit_count, 2);
+        assert!(repo.last_visited_at.is_some());
+    }
+
+    #[test]
+    fn test_find_frecency_breaks_ties_within_match_kind() {
+        let mut data = create_test_data();
+        visit(&mut data, "/base/github.com/user/my-prog-tools", 5);
+
+        let results = data.find("prog", false);
+        let repo_names: Vec<&str> = results.iter().map(|r| r.repo.repo.as_str()).collect();
+
+        // The exact match still wins; among the "contains" matches the visited repo goes first.
+        assert_eq!(repo_names, vec!["prog", "my-prog-tools", "prog-cli"]);
+    }
+
+    #[test]
+    fn test_find_rank_by_frecency_outranks_match_kind() {
+        let mut data = create_test_data();
+        visit(&mut data, "/base/github.com/user/my-prog-tools", 5);
+
+        let results = data.find("prog", true);
+
+        assert_eq!(results[0].repo.repo, "my-prog-tools");
+        assert_eq!(results[0].match_kind, MatchKind::RepoContains);
+        assert_eq!(results[1].repo.repo, "prog");
+    }
+
+    #[test]
+    fn test_find_by_tag() {
+        let mut data = create_test_data();
+        let tags = vec!["infra".to_string(), "work".to_string()];
+        assert!(data.add_tags("/base/github.com/user/vscode", &tags));
+        assert!(!data.add_tags("/base/github.com/user/missing", &tags));
+
+        let results = data.find("#infra", false);
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].repo.repo, "vscode");
+        assert_eq!(results[0].match_kind, MatchKind::Tag);
+
+        // A plain keyword matches tags too, after every other kind of match.
+        let results = data.find("work", false);
+        assert_eq!(results[0].repo.repo, "vscode");
+        assert_eq!(results[0].match_kind, MatchKind::Tag);
+
+        assert!(data.remove_tags("/base/github.com/user/vscode", &["infra".to_string()]));
+        assert!(data.find("#infra", false).is_empty());
+    }
+
+    #[test]
+    fn test_find_subsequence() {
+        let data = create_test_data();
+
+        let results = data.find("prgcli", false);
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].repo.repo, "prog-cli");
+        assert_eq!(results[0].match_kind, MatchKind::Subsequence);
+
+        // Boundary matches ("my-prog-tools") outrank scattered ones ("vscode").
+        let results = data.find("mpt", false);
+        assert_eq!(results[0].repo.repo, "my-prog-tools");
+        assert!(data.find("zzz", false).is_empty());
+    }
+
+    #[test]
+    fn test_find_exact_name_ignores_subsequences() {
+        let mut data = Data::new();
+        for name in ["web", "weather-bot"] {
+            data.record_item(
+                "/base",
+                &format!("https://github.com/acme/{}.git", name),
+                "github.com",
+                name,
+                "acme",
+                &format!("/base/github.com/acme/{}", name),
+            );
+        }
+
+        let results = data.find("web", false);
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].repo.repo, "web");
+        assert_eq!(results[0].match_kind, MatchKind::RepoExact);
+
+        // Without a better match the subsequence is still found.
+        let results = data.find("wthrbt", false);
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].repo.repo, "weather-bot");
+    }
+
+    #[test]
+    fn test_find_matches_any_remote() {
+        let mut data = create_test_data();
+        data.update_record("/base/github.com/user/vscode", |record| {
+            record.remotes = vec![
+                RepoRemote::new("origin", "https://github.com/user/vscode.git"),
+                RepoRemote::new("upstream", "https://github.com/microsoft/vscode.git"),
+            ];
+        });
+
+        let results = data.find("microsoft/vscode", false);
+        assert_eq!(results[0].repo.repo, "vscode");
+        assert_eq!(results[0].match_kind, MatchKind::FullNameExact);
+
+        let results = data.find("microsoft", false);
+        assert_eq!(results[0].match_kind, MatchKind::OwnerExact);
+    }
+
+    #[test]
+    fn test_find_multiple_terms() {
+        let mut data = create_test_data();
+
+        let results = data.find("prog cli", false);
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].repo.repo, "prog-cli");
+        assert!(data.find("prog zzz", false).is_empty());
+
+        // Ranked by all terms together, not only by the weakest one:
+        // owner + remote (4 + 6) loses to repo name + tag (0 + 9).
+        data.record_item("/w", "https://acme.dev/api/web.git", "acme.dev", "web", "acme", "/w/web");
+        data.record_item(
+            "/w",
+            "https://corp.dev/corp/api.git",
+            "corp.dev",
+            "api",
+            "corp",
+            "/w/api",
+        );
+        data.add_tags("/w/api", &["acme".to_string()]);
+        let results = data.find("acme api", false);
+        let names: Vec<&str> = results.iter().map(|m| m.repo.repo.as_str()).collect();
+        assert_eq!(names, vec!["api", "web"]);
+        assert_eq!(results[0].match_kind, MatchKind::Tag);
+    }
+
+    #[test]
+    fn test_add_tags_dedupes_and_sorts() {
+        let mut data = create_test_data();
+        let path = "/base/github.com/user/prog";
+        data.add_tags(path, &["work".to_string(), "infra".to_string()]);
+        data.add_tags(path, &["work".to_string()]);
+
+        assert_eq!(data.records.get(path).unwrap().tags, vec!["infra", "work"]);
+    }
+
+    #[test]
+    fn test_set_note() {
+        let mut data = create_test_data();
+        let path = "/base/github.com/user/prog";
+
+        assert!(data.set_note(path, Some("deploys on fridays".to_string())));
+        assert_eq!(data.records.get(path).unwrap().note.as_deref(), Some("deploys on fridays"));
+
+        assert!(data.set_note(path, Some("  ".to_string())));
+        assert_eq!(data.records.get(path).unwrap().note, None);
+    }
+
+    #[test]
+    fn test_learned_choices() {
+        let mut data = create_test_data();
+        let candidates = vec!["/base/b".to_string(), "/base/a".to_string()];
+        data.learn_choice("acme  API", candidates.clone(), "/base/b");
+        data.learn_choice("web", vec!["/base/c".to_string(), "/base/d".to_string()], "/base/c");
+
+        let reordered = vec!["/base/a".to_string(), "/base/b".to_string()];
+        assert_eq!(data.learned_choice("acme api", &reordered).as_deref(), Some("/base/b"));
+        // A new candidate means the choice has to be made again.
+        let grown = vec!["/base/a".to_string(), "/base/b".to_string(), "/base/e".to_string()];
+        assert_eq!(data.learned_choice("acme api", &grown), None);
+
+        data.learn_choice("acme api", candidates, "/base/a");
+        assert_eq!(data.learned_choice("acme api", &reordered).as_deref(), Some("/base/a"));
+        assert_eq!(data.choices.len(), 2);
+
+        assert_eq!(data.forget_choices(Some("ACME api")), 1);
+        assert_eq!(data.learned_choice("acme api", &reordered), None);
+        assert_eq!(data.forget_choices(None), 1);
+        assert!(data.choices.is_empty());
+    }
+
     #[test]
     fn test_find_fuzzy_segments_match() {
         let mut data = Data::new();
@@ -486,7 +1352,7 @@ mod tests {
 
         // "abcd-jkl" should fuzzy match "abcd-efg-jkl" because segments "abcd" and "jkl"
         // appear in order in the target
-        let results = data.find("abcd-jkl");
+        let results = data.find("abcd-jkl", false);
 
         assert!(!results.is_empty(), "Should find fuzzy match for abcd-jkl");
         assert_eq!(results[0].repo.repo, "abcd-efg-jkl");
@@ -544,7 +1410,7 @@ mod tests {
             "/base/github.com/user/abcd-efg-jkl",
         );
 
-        let results = data.find("abcd-jkl");
+        let results = data.find("abcd-jkl", false);
 
         // "abcd-jkl" should be exact match (rank 0), "abcd-efg-jkl" should be fuzzy (rank 7)
         assert_eq!(results[0].repo.repo, "abcd-jkl");
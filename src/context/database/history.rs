@@ -0,0 +1,151 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use chrono::NaiveDateTime;
+
+/// First line of every snapshot; the rest of the line is the command that
+/// was about to change the index.
+const HEADER: &str = "# prog snapshot: ";
+const ID_FORMAT: &str = "%Y%m%d-%H%M%S%3f";
+
+/// A copy of the index taken right before a command changed it.
+#[derive(Debug, Clone)]
+pub struct Snapshot {
+    pub id: String,
+    pub command: String,
+    pub created_at: Option<NaiveDateTime>,
+    pub records: usize,
+    pub path: PathBuf,
+}
+
+/// Stores `contents` as a new snapshot in `dir` and drops the oldest ones so
+/// at most `keep` remain. Callers must hold the writer lock.
+pub(crate) fn save(dir: &Path, command: &str, contents: &str, keep: usize) -> Result<(), String> {
+    fs::create_dir_all(dir).map_err(|e| format!("Unable to create history directory: {}", e))?;
+
+    let base_id = chrono::Local::now().naive_local().format(ID_FORMAT).to_string();
+    let mut id = base_id.clone();
+    let mut n = 1;
+    while dir.join(format!("{}.toml", id)).exists() {
+        id = format!("{}-{}", base_id, n);
+        n += 1;
+    }
+
+    let body = format!("{}{}\n{}", HEADER, command, contents);
+    fs::write(dir.join(format!("{}.toml", id)), body)
+        .map_err(|e| format!("Unable to write snapshot: {}", e))?;
+
+    for old in list(dir).into_iter().skip(keep) {
+        if let Err(e) = fs::remove_file(&old.path) {
+            log::warn!("Unable to remove old snapshot {}: {}", old.path.display(), e);
+        }
+    }
+    Ok(())
+}
+
+/// Lists the snapshots in `dir`, newest first.
+pub(crate) fn list(dir: &Path) -> Vec<Snapshot> {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return Vec::new();
+    };
+
+    let mut snapshots: Vec<Snapshot> = entries
+        .flatten()
+        .filter_map(|entry| {
+            let path = entry.path();
+            let id = path.file_name()?.to_str()?.strip_suffix(".toml")?.to_string();
+            let contents = fs::read_to_string(&path).ok()?;
+            let command = contents.lines().next()?.strip_prefix(HEADER)?.to_string();
+            let records = contents.parse::<toml::Table>().ok().map_or(0, |table| {
+                table.get("records").and_then(|r| r.as_array()).map_or(0, |r| r.len())
+            });
+            let created_at =
+                NaiveDateTime::parse_from_str(id.get(..18).unwrap_or(&id), ID_FORMAT).ok();
+            Some(Snapshot { id, command, created_at, records, path })
+        })
+        .collect();
+
+    // Newest first.
+    snapshots.sort_by(|a, b| sort_key(&b.id).cmp(&sort_key(&a.id)));
+    snapshots
+}
+
+/// Ids are a sortable timestamp, followed by `-<n>` when several snapshots
+/// were taken in the same millisecond. The counter is compared as a number,
+/// so that `-10` comes after `-2`.
+fn sort_key(id: &str) -> (&str, u32) {
+    match id.rsplit_once('-') {
+        Some((timestamp, n)) if timestamp.contains('-') => (timestamp, n.parse().unwrap_or(0)),
+        _ => (id, 0),
+    }
+}
+
+/// Finds the snapshot whose id is `id`, or uniquely starts with it.
+pub(crate) fn find(dir: &Path, id: &str) -> Result<Snapshot, String> {
+    let snapshots = list(dir);
+    if let Some(exact) = snapshots.iter().find(|s| s.id == id) {
+        return Ok(exact.clone());
+    }
+
+    let mut matches = snapshots.into_iter().filter(|s| s.id.starts_with(id));
+    match (matches.next(), matches.next()) {
+        (Some(snapshot), None) => Ok(snapshot),
+        (Some(_), Some(_)) => Err(format!("Snapshot id {} is ambiguous", id)),
+        (None, _) => Err(format!("No snapshot with id {}", id)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_save_lists_newest_first_and_rotates() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let dir = temp_dir.path().join("history");
+
+        for command in ["add", "tag", "clean"] {
+            save(&dir, command, "[[records]]\nfull_path = \"/a\"\n", 2).unwrap();
+        }
+
+        let snapshots = list(&dir);
+        assert_eq!(snapshots.len(), 2, "only the newest snapshots are kept");
+        assert_eq!(snapshots[0].command, "clean");
+        assert_eq!(snapshots[1].command, "tag");
+        assert_eq!(snapshots[0].records, 1);
+        assert!(snapshots[0].created_at.is_some());
+    }
+
+    #[test]
+    fn test_list_orders_same_millisecond_snapshots_by_counter() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        for id in ["20250101-120000123", "20250101-120000123-2", "20250101-120000123-10"] {
+            fs::write(temp_dir.path().join(format!("{}.toml", id)), format!("{}{}\n", HEADER, id))
+                .unwrap();
+        }
+        fs::write(temp_dir.path().join("20241231-235959999-11.toml"), format!("{}old\n", HEADER))
+            .unwrap();
+
+        let ids: Vec<String> = list(temp_dir.path()).into_iter().map(|s| s.id).collect();
+        assert_eq!(
+            ids,
+            vec![
+                "20250101-120000123-10",
+                "20250101-120000123-2",
+                "20250101-120000123",
+                "20241231-235959999-11",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_find_by_prefix() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        save(temp_dir.path(), "sync", "", 10).unwrap();
+        let id = list(temp_dir.path())[0].id.clone();
+
+        assert_eq!(find(temp_dir.path(), &id).unwrap().command, "sync");
+        assert_eq!(find(temp_dir.path(), &id[..8]).unwrap().id, id);
+        assert!(find(temp_dir.path(), "1999").is_err());
+    }
+}
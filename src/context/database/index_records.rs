@@ -47,10 +47,19 @@ impl IndexedRecords {
         self.records.remove(path).is_some()
     }
 
+    /// Removes a repository record by its path and returns it
+    pub(crate) fn take(&mut self, path: &str) -> Option<Repo> {
+        self.records.remove(path)
+    }
+
     pub(crate) fn get(&self, path: &str) -> Option<&Repo> {
         self.records.get(path)
     }
 
+    pub(crate) fn get_mut(&mut self, path: &str) -> Option<&mut Repo> {
+        self.records.get_mut(path)
+    }
+
     /// Returns a vector containing all records in sorted order by path
     ///
     /// # Returns
@@ -162,6 +171,7 @@ mod tests {
             remote_url: format!("https://github.com/user/{}.git", repo),
             base_dir: "/base".to_string(),
             full_path: full_path.to_string(),
+            ..Default::default()
         }
     }
 
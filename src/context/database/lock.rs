@@ -0,0 +1,74 @@
+use std::fs::{File, OpenOptions};
+use std::path::Path;
+
+/// Advisory lock on the database lock file.
+///
+/// Every process that reads or writes `data.toml` takes this lock first, so two
+/// shells running `p` at the same time never observe or produce a half-written
+/// index. The lock is released when the guard is dropped.
+pub(crate) struct DbLock {
+    file: File,
+}
+
+impl DbLock {
+    /// Blocks until an exclusive (writer) lock is acquired.
+    pub(crate) fn exclusive(path: &Path) -> Result<Self, String> {
+        let file = Self::open(path)?;
+        file.lock().map_err(|e| format!("Unable to lock database: {}", e))?;
+        Ok(Self { file })
+    }
+
+    /// Blocks until a shared (reader) lock is acquired.
+    pub(crate) fn shared(path: &Path) -> Result<Self, String> {
+        let file = Self::open(path)?;
+        file.lock_shared().map_err(|e| format!("Unable to lock database: {}", e))?;
+        Ok(Self { file })
+    }
+
+    fn open(path: &Path) -> Result<File, String> {
+        OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(path)
+            .map_err(|e| format!("Unable to open database lock file: {}", e))
+    }
+}
+
+impl Drop for DbLock {
+    fn drop(&mut self) {
+        let _ = self.file.unlock();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_exclusive_lock_blocks_other_writers() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let lock_path = temp_dir.path().join("data.lock");
+
+        let guard = DbLock::exclusive(&lock_path).unwrap();
+
+        let other = File::open(&lock_path).unwrap();
+        assert!(other.try_lock().is_err(), "second writer should not get the lock");
+
+        drop(guard);
+        assert!(other.try_lock().is_ok(), "lock should be released on drop");
+    }
+
+    #[test]
+    fn test_shared_locks_allow_concurrent_readers() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let lock_path = temp_dir.path().join("data.lock");
+
+        let _reader = DbLock::shared(&lock_path).unwrap();
+
+        let other = File::open(&lock_path).unwrap();
+        assert!(other.try_lock_shared().is_ok(), "readers should share the lock");
+        assert!(File::open(&lock_path).unwrap().try_lock().is_err());
+    }
+}
@@ -0,0 +1,207 @@
+use toml::{Table, Value};
+
+/// Schema version of the index written by this build.
+pub const CURRENT_VERSION: u32 = 8;
+
+/// A single upgrade step from schema `from` to `from + 1`.
+pub struct Migration {
+    pub from: u32,
+    pub description: &'static str,
+    run: fn(&mut Table) -> Result<(), String>,
+}
+
+/// Every upgrade step, in order. A step receives the raw TOML table of the
+/// index and must leave it deserializable as schema `from + 1`.
+const MIGRATIONS: &[Migration] = &[
+    Migration {
+        from: 1,
+        description: "Replace the legacy \"1.0\" version string with an integer schema version",
+        run: v1_to_v2,
+    },
+    Migration { from: 2, description: "Add visit counts to records", run: v2_to_v3 },
+    Migration { from: 3, description: "Add user tags to records", run: v3_to_v4 },
+    Migration { from: 4, description: "Record git clone options on records", run: v4_to_v5 },
+    Migration {
+        from: 5,
+        description: "Track every git remote, seeded with the recorded origin",
+        run: v5_to_v6,
+    },
+    Migration { from: 6, description: "Remember choices between ambiguous matches", run: v6_to_v7 },
+    Migration { from: 7, description: "Record the submodules of repositories", run: v7_to_v8 },
+];
+
+/// Reads the schema version of a raw index table.
+///
+/// Files written before schema versioning carry `version = "1.0"` (or no
+/// version at all) and are treated as schema 1.
+pub(crate) fn detect_version(table: &Table) -> Result<u32, String> {
+    match table.get("version") {
+        None => Ok(1),
+        Some(Value::String(s)) if s == "1.0" => Ok(1),
+        Some(Value::Integer(n)) if *n >= 1 => {
+            u32::try_from(*n).map_err(|_| format!("Invalid database version: {}", n))
+        }
+        Some(other) => Err(format!("Invalid database version: {}", other)),
+    }
+}
+
+/// Returns the migrations needed to bring schema `version` up to date.
+pub(crate) fn pending(version: u32) -> Vec<&'static Migration> {
+    MIGRATIONS.iter().filter(|m| m.from >= version && m.from < CURRENT_VERSION).collect()
+}
+
+/// Upgrades `table` in place, one step at a time, up to [`CURRENT_VERSION`].
+pub(crate) fn migrate(table: &mut Table) -> Result<(), String> {
+    let version = detect_version(table)?;
+    for migration in pending(version) {
+        (migration.run)(table).map_err(|e| {
+            format!("Migration v{} -> v{} failed: {}", migration.from, migration.from + 1, e)
+        })?;
+        table.insert("version".to_string(), Value::Integer(i64::from(migration.from + 1)));
+    }
+    Ok(())
+}
+
+fn v1_to_v2(table: &mut Table) -> Result<(), String> {
+    table.remove("version");
+    Ok(())
+}
+
+fn v2_to_v3(table: &mut Table) -> Result<(), String> {
+    for_each_record(table, |record| {
+        record.entry("visit_count").or_insert(Value::Integer(0));
+    })
+}
+
+fn v3_to_v4(table: &mut Table) -> Result<(), String> {
+    for_each_record(table, |record| {
+        record.entry("tags").or_insert(Value::Array(Vec::new()));
+    })
+}
+
+fn v4_to_v5(table: &mut Table) -> Result<(), String> {
+    for_each_record(table, |record| {
+        record.entry("clone_args").or_insert(Value::Array(Vec::new()));
+    })
+}
+
+fn v5_to_v6(table: &mut Table) -> Result<(), String> {
+    for_each_record(table, |record| {
+        if record.contains_key("remotes") {
+            return;
+        }
+        let field = |key: &str| record.get(key).cloned().unwrap_or(Value::String(String::new()));
+        let mut origin = Table::new();
+        origin.insert("name".to_string(), Value::String("origin".to_string()));
+        origin.insert("url".to_string(), field("remote_url"));
+        origin.insert("host".to_string(), field("host"));
+        origin.insert("owner".to_string(), field("owner"));
+        origin.insert("repo".to_string(), field("repo"));
+        record.insert("remotes".to_string(), Value::Array(vec![Value::Table(origin)]));
+    })
+}
+
+fn v6_to_v7(table: &mut Table) -> Result<(), String> {
+    table.entry("choices").or_insert(Value::Array(Vec::new()));
+    Ok(())
+}
+
+fn v7_to_v8(table: &mut Table) -> Result<(), String> {
+    for_each_record(table, |record| {
+        record.entry("submodules").or_insert(Value::Array(Vec::new()));
+    })
+}
+
+/// Runs `f` on every record table of the index.
+fn for_each_record<F>(table: &mut Table, mut f: F) -> Result<(), String>
+where
+    F: FnMut(&mut Table),
+{
+    match table.get_mut("records") {
+        None => Ok(()),
+        Some(Value::Array(records)) => {
+            for record in records {
+                match record {
+                    Value::Table(record) => f(record),
+                    other => return Err(format!("Invalid record: {}", other)),
+                }
+            }
+            Ok(())
+        }
+        Some(other) => Err(format!("Invalid records: {}", other)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::models::Repo;
+    use super::*;
+
+    const V1_DATABASE: &str = r#"
+version = "1.0"
+last_sync_time = "2024-01-01T00:00:00"
+
+[[records]]
+created_at = "2024-01-01T00:00:00"
+updated_at = "2024-01-01T00:00:00"
+host = "github.com"
+repo = "prog"
+owner = "bytemain"
+remote_url = "https://github.com/bytemain/prog.git"
+base_dir = "/base"
+full_path = "/base/github.com/bytemain/prog"
+"#;
+
+    #[test]
+    fn test_detect_version() {
+        let legacy: Table = toml::from_str(V1_DATABASE).unwrap();
+        assert_eq!(detect_version(&legacy).unwrap(), 1);
+
+        let missing: Table = toml::from_str("records = []").unwrap();
+        assert_eq!(detect_version(&missing).unwrap(), 1);
+
+        let current: Table = toml::from_str("version = 8").unwrap();
+        assert_eq!(detect_version(&current).unwrap(), 8);
+
+        let invalid: Table = toml::from_str("version = \"banana\"").unwrap();
+        assert!(detect_version(&invalid).is_err());
+    }
+
+    #[test]
+    fn test_pending_lists_steps_in_order() {
+        let steps: Vec<u32> = pending(1).iter().map(|m| m.from).collect();
+        assert_eq!(steps, (1..CURRENT_VERSION).collect::<Vec<_>>());
+        assert!(pending(CURRENT_VERSION).is_empty());
+    }
+
+    #[test]
+    fn test_migrations_cover_every_version() {
+        for (i, migration) in MIGRATIONS.iter().enumerate() {
+            assert_eq!(migration.from, i as u32 + 1, "migrations must be contiguous");
+        }
+        assert_eq!(MIGRATIONS.len() as u32, CURRENT_VERSION - 1);
+    }
+
+    #[test]
+    fn test_migrate_v1_database() {
+        let mut table: Table = toml::from_str(V1_DATABASE).unwrap();
+
+        migrate(&mut table).unwrap();
+
+        assert_eq!(detect_version(&table).unwrap(), CURRENT_VERSION);
+        let record = &table["records"].as_array().unwrap()[0];
+        assert_eq!(record.get("visit_count"), Some(&Value::Integer(0)));
+        assert_eq!(record.get("tags"), Some(&Value::Array(Vec::new())));
+        assert_eq!(record.get("clone_args"), Some(&Value::Array(Vec::new())));
+        assert_eq!(record.get("submodules"), Some(&Value::Array(Vec::new())));
+        assert_eq!(table.get("choices"), Some(&Value::Array(Vec::new())));
+        let serialized = toml::to_string(&table).unwrap();
+        assert!(toml::from_str::<super::super::core::Data>(&serialized).is_ok());
+        let repo: Repo = record.clone().try_into().unwrap();
+        let remotes = &repo.remotes;
+        assert_eq!(remotes.len(), 1);
+        assert_eq!(remotes[0].name, "origin");
+        assert_eq!(remotes[0].url, "https://github.com/bytemain/prog.git");
+        assert_eq!(remotes[0].owner, "bytemain");
+    }
+}
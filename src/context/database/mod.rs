@@ -1,6 +1,12 @@
 mod core;
+mod history;
 mod index_records;
+mod lock;
+mod migrations;
 pub mod models;
+mod query;
 
 pub use core::{Database, MatchKind};
+pub use migrations::CURRENT_VERSION;
+pub use query::Query;
 pub type MatchedRepo = core::MatchedRepo;
@@ -2,7 +2,70 @@ use std::path::PathBuf;
 
 use serde::{Deserialize, Serialize};
 
-#[derive(Serialize, Deserialize, Debug, Clone)]
+use crate::helpers::git::{Submodule, parse_git_url, remote_url_is_valid};
+use crate::helpers::layout;
+
+/// A git remote of a repository. `host`, `owner` and `repo` are parsed from
+/// the URL and left empty when it cannot be parsed.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+pub struct RepoRemote {
+    pub name: String,
+    pub url: String,
+    #[serde(default)]
+    pub host: String,
+    #[serde(default)]
+    pub owner: String,
+    #[serde(default)]
+    pub repo: String,
+}
+
+impl RepoRemote {
+    pub fn new(name: &str, url: &str) -> Self {
+        let mut remote =
+            RepoRemote { name: name.to_string(), url: url.to_string(), ..Default::default() };
+        if let Some(parsed) = parse_git_url(url).filter(remote_url_is_valid)
+            && !parsed.name.trim().is_empty()
+        {
+            remote.host = parsed.host.unwrap_or_default();
+            remote.owner = parsed.owner.unwrap_or_default();
+            remote.repo = parsed.name;
+        }
+        remote
+    }
+
+    /// Whether host, owner and name could be parsed from the URL.
+    pub fn is_parsed(&self) -> bool {
+        !self.host.is_empty() && !self.owner.is_empty() && !self.repo.is_empty()
+    }
+}
+
+/// Host recorded for repositories without any remote.
+pub const LOCAL_HOST: &str = "local";
+
+/// Picks the remote that decides where a repository lives: the first parsed
+/// remote in `preferred` order, otherwise the first parsed remote.
+pub fn primary_remote<'a>(
+    remotes: &'a [RepoRemote],
+    preferred: &[String],
+) -> Option<&'a RepoRemote> {
+    preferred
+        .iter()
+        .find_map(|name| remotes.iter().find(|r| &r.name == name && r.is_parsed()))
+        .or_else(|| remotes.iter().find(|r| r.is_parsed()))
+}
+
+/// The result picked the last time a keyword matched several repositories.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct LearnedChoice {
+    /// The keyword, lowercased with single spaces.
+    pub keyword: String,
+    /// Sorted paths that were offered. The choice only applies to the same set.
+    pub candidates: Vec<String>,
+    pub path: String,
+    pub chosen_at: chrono::naive::NaiveDateTime,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
 pub struct Repo {
     pub created_at: chrono::naive::NaiveDateTime,
     pub updated_at: chrono::naive::NaiveDateTime,
@@ -12,16 +75,165 @@ pub struct Repo {
     pub remote_url: String,
     pub base_dir: String,
     pub full_path: String,
+    /// How many times the repository was picked by `find` or entered through the shell hook.
+    #[serde(default)]
+    pub visit_count: u32,
+    #[serde(default)]
+    pub last_visited_at: Option<chrono::naive::NaiveDateTime>,
+    /// User-assigned tags, normalized to lowercase.
+    #[serde(default)]
+    pub tags: Vec<String>,
+    /// Free-form user note.
+    #[serde(default)]
+    pub note: Option<String>,
+    /// Extra arguments passed to `git clone` when the repository was added.
+    #[serde(default)]
+    pub clone_args: Vec<String>,
+    /// Every remote of the repository. `remote_url` is the primary one.
+    #[serde(default)]
+    pub remotes: Vec<RepoRemote>,
+    /// Path of the main working tree when this is a linked git worktree.
+    #[serde(default)]
+    pub worktree_of: Option<String>,
+    /// Submodules declared in the repository's `.gitmodules`.
+    #[serde(default)]
+    pub submodules: Vec<Submodule>,
 }
 
 impl Repo {
-    pub fn owner_fs_path(&self) -> String {
-        let path = PathBuf::new().join(&self.base_dir).join(&self.host).join(&self.owner);
-        path.to_str().unwrap().to_string()
+    /// Zoxide-style frecency: the visit count weighted by how recently the
+    /// repository was last visited.
+    pub fn frecency(&self, now: chrono::naive::NaiveDateTime) -> f64 {
+        let Some(last_visited_at) = self.last_visited_at else {
+            return 0.0;
+        };
+
+        let age = now - last_visited_at;
+        let weight = if age.num_hours() < 1 {
+            4.0
+        } else if age.num_days() < 1 {
+            2.0
+        } else if age.num_weeks() < 1 {
+            0.5
+        } else {
+            0.25
+        };
+
+        f64::from(self.visit_count) * weight
+    }
+
+    /// Whether the repository has no remote, so it was never pushed anywhere.
+    pub fn is_local(&self) -> bool {
+        self.host == LOCAL_HOST && self.remote_url.is_empty()
+    }
+
+    pub fn has_tag(&self, tag: &str) -> bool {
+        let tag = normalize_tag(tag);
+        self.tags.contains(&tag)
+    }
+
+    /// Directory holding the repositories of this owner under `layout`, if it has one.
+    pub fn owner_fs_path(&self, layout: &str) -> Option<String> {
+        self.layout_fs_path(layout, "{owner}")
+    }
+
+    /// Directory holding the repositories of this host under `layout`, if it has one.
+    pub fn host_fs_path(&self, layout: &str) -> Option<String> {
+        self.layout_fs_path(layout, "{host}")
+    }
+
+    fn layout_fs_path(&self, layout: &str, placeholder: &str) -> Option<String> {
+        let prefix = layout::prefix(layout, placeholder, &self.host, &self.owner)?;
+        let path = PathBuf::new().join(&self.base_dir).join(prefix);
+        Some(path.to_str().unwrap().to_string())
+    }
+}
+
+/// Normalizes a user supplied tag: trimmed, lowercase, without a leading `#`.
+pub fn normalize_tag(tag: &str) -> String {
+    tag.trim().trim_start_matches('#').trim().to_lowercase()
+}
+
+/// Splits a comma separated tag list (`work,infra`) into normalized tags.
+pub fn parse_tags(input: &str) -> Vec<String> {
+    input.split(',').map(normalize_tag).filter(|tag| !tag.is_empty()).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+
+    fn visited(visit_count: u32, ago: Duration) -> (Repo, chrono::naive::NaiveDateTime) {
+        let now = chrono::Utc::now().naive_utc();
+        let repo = Repo { visit_count, last_visited_at: Some(now - ago), ..Default::default() };
+        (repo, now)
+    }
+
+    #[test]
+    fn test_frecency_never_visited_is_zero() {
+        let repo = Repo::default();
+        assert_eq!(repo.frecency(chrono::Utc::now().naive_utc()), 0.0);
+    }
+
+    #[test]
+    fn test_frecency_decays_with_age() {
+        let (repo, now) = visited(10, Duration::minutes(5));
+        assert_eq!(repo.frecency(now), 40.0);
+
+        let (repo, now) = visited(10, Duration::hours(3));
+        assert_eq!(repo.frecency(now), 20.0);
+
+        let (repo, now) = visited(10, Duration::days(3));
+        assert_eq!(repo.frecency(now), 5.0);
+
+        let (repo, now) = visited(10, Duration::weeks(20));
+        assert_eq!(repo.frecency(now), 2.5);
+    }
+
+    #[test]
+    fn test_frecency_frequent_old_beats_single_recent() {
+        let (frequent, now) = visited(50, Duration::days(3));
+        let (recent, _) = visited(1, Duration::minutes(1));
+        assert!(frequent.frecency(now) > recent.frecency(now));
+    }
+
+    #[test]
+    fn test_parse_tags() {
+        assert_eq!(parse_tags("work,infra"), vec!["work", "infra"]);
+        assert_eq!(parse_tags(" Work , #Infra,, "), vec!["work", "infra"]);
+        assert!(parse_tags("").is_empty());
+    }
+
+    #[test]
+    fn test_repo_remote_parses_url() {
+        let remote = RepoRemote::new("upstream", "git@github.com:bytemain/prog.git");
+        assert_eq!(remote.host, "github.com");
+        assert_eq!(remote.owner, "bytemain");
+        assert_eq!(remote.repo, "prog");
+
+        assert!(!RepoRemote::new("local", "/srv/git/thing").is_parsed());
+    }
+
+    #[test]
+    fn test_primary_remote_follows_preferred_order() {
+        let remotes = vec![
+            RepoRemote::new("fork", "https://github.com/me/prog.git"),
+            RepoRemote::new("upstream", "https://github.com/bytemain/prog.git"),
+            RepoRemote::new("origin", "/srv/git/prog"),
+        ];
+        let preferred = vec!["origin".to_string(), "upstream".to_string()];
+
+        // `origin` is preferred but cannot be parsed, so `upstream` wins.
+        assert_eq!(primary_remote(&remotes, &preferred).unwrap().name, "upstream");
+        assert_eq!(primary_remote(&remotes, &[]).unwrap().name, "fork");
+        assert!(primary_remote(&remotes[2..], &preferred).is_none());
     }
 
-    pub fn host_fs_path(&self) -> String {
-        let path = PathBuf::new().join(&self.base_dir).join(&self.host);
-        path.to_str().unwrap().to_string()
+    #[test]
+    fn test_has_tag_is_case_insensitive() {
+        let repo = Repo { tags: vec!["infra".to_string()], ..Default::default() };
+        assert!(repo.has_tag("#Infra"));
+        assert!(!repo.has_tag("work"));
     }
 }
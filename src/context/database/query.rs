@@ -0,0 +1,284 @@
+use super::core::{MatchKind, match_kind};
+use super::models::Repo;
+use crate::helpers::git::get_branch;
+use crate::helpers::path::expand_tilde;
+use globset::{GlobBuilder, GlobMatcher};
+
+/// A parsed search query such as `api owner:acme -tag:archived`.
+///
+/// Terms are separated by whitespace and must all match. A term is either a
+/// plain keyword, matched like a classic `find` keyword, or a `field:value`
+/// qualifier. Values may be globs (`branch:release/*`). A leading `-` negates
+/// a term.
+#[derive(Debug, Clone, Default)]
+pub struct Query {
+    terms: Vec<Term>,
+}
+
+#[derive(Debug, Clone)]
+struct Term {
+    negated: bool,
+    matcher: Matcher,
+}
+
+#[derive(Debug, Clone)]
+enum Matcher {
+    Keyword(String),
+    Field(Field, Pattern),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Field {
+    Host,
+    Owner,
+    Name,
+    Path,
+    Branch,
+    Tag,
+}
+
+impl Field {
+    fn parse(name: &str) -> Option<Self> {
+        match name {
+            "host" => Some(Field::Host),
+            "owner" => Some(Field::Owner),
+            "name" | "repo" => Some(Field::Name),
+            "path" => Some(Field::Path),
+            "branch" => Some(Field::Branch),
+            "tag" => Some(Field::Tag),
+            _ => None,
+        }
+    }
+}
+
+/// A qualifier value: a case-insensitive glob when it contains glob
+/// characters, a literal otherwise.
+#[derive(Debug, Clone)]
+struct Pattern {
+    text: String,
+    glob: Option<GlobMatcher>,
+}
+
+impl Pattern {
+    fn new(text: &str) -> Self {
+        let text = text.to_lowercase();
+        let glob = if text.contains(['*', '?', '[', '{']) {
+            GlobBuilder::new(&text)
+                .case_insensitive(true)
+                .build()
+                .map(|glob| glob.compile_matcher())
+                .ok()
+        } else {
+            None
+        };
+        Pattern { text, glob }
+    }
+
+    fn matches(&self, value: &str) -> bool {
+        match &self.glob {
+            Some(glob) => glob.is_match(value),
+            None => value.to_lowercase() == self.text,
+        }
+    }
+
+    /// Literal paths match the directory itself and anything below it.
+    fn matches_path(&self, path: &str) -> bool {
+        if self.glob.is_some() {
+            return self.matches(path);
+        }
+        let path = path.to_lowercase();
+        let prefix = self.text.trim_end_matches(['/', '\\']);
+        path == prefix
+            || path.strip_prefix(prefix).is_some_and(|rest| rest.starts_with(['/', '\\']))
+    }
+}
+
+impl Query {
+    pub fn parse(input: &str) -> Self {
+        let terms = input
+            .split_whitespace()
+            .map(|raw| {
+                let (negated, body) = match raw.strip_prefix('-') {
+                    Some(body) if !body.is_empty() => (true, body),
+                    _ => (false, raw),
+                };
+                let qualifier = body.split_once(':').and_then(|(name, value)| {
+                    let field = Field::parse(&name.to_lowercase())?;
+                    (!value.is_empty()).then_some((field, value))
+                });
+                let matcher = match qualifier {
+                    Some((Field::Path, value)) => {
+                        Matcher::Field(Field::Path, Pattern::new(&expand_tilde(value)))
+                    }
+                    Some((field, value)) => Matcher::Field(field, Pattern::new(value)),
+                    None => Matcher::Keyword(body.to_lowercase()),
+                };
+                Term { negated, matcher }
+            })
+            .collect();
+        Query { terms }
+    }
+
+    /// The plain keywords of the query, used to rank results by similarity.
+    pub fn keywords(&self) -> String {
+        let keywords: Vec<&str> = self
+            .terms
+            .iter()
+            .filter(|term| !term.negated)
+            .filter_map(|term| match &term.matcher {
+                Matcher::Keyword(keyword) => Some(keyword.as_str()),
+                Matcher::Field(..) => None,
+            })
+            .collect();
+        keywords.join(" ")
+    }
+
+    pub fn matches(&self, repo: &Repo) -> bool {
+        self.match_kind(repo).is_some()
+    }
+
+    /// Checks every term against `repo`. The result is the weakest way a
+    /// keyword matched, or [`MatchKind::Filter`] when there are only qualifiers.
+    pub(crate) fn match_kind(&self, repo: &Repo) -> Option<MatchKind> {
+        let kinds = self.keyword_kinds(repo)?;
+        Some(
+            kinds
+                .into_iter()
+                .map(|(_, kind)| kind)
+                .max_by_key(MatchKind::rank)
+                .unwrap_or(MatchKind::Filter),
+        )
+    }
+
+    /// Checks every term against `repo` and returns how each positive keyword
+    /// matched, in query order, or `None` when any term rules the repo out.
+    pub(crate) fn keyword_kinds(&self, repo: &Repo) -> Option<Vec<(&str, MatchKind)>> {
+        let mut kinds = Vec::new();
+        let mut branch: Option<String> = None;
+
+        for term in &self.terms {
+            match &term.matcher {
+                Matcher::Keyword(keyword) => match (term.negated, match_kind(repo, keyword)) {
+                    (true, Some(_)) | (false, None) => return None,
+                    (true, None) => {}
+                    (false, Some(kind)) => kinds.push((keyword.as_str(), kind)),
+                },
+                Matcher::Field(field, pattern) => {
+                    if field_matches(repo, *field, pattern, &mut branch) == term.negated {
+                        return None;
+                    }
+                }
+            }
+        }
+
+        Some(kinds)
+    }
+}
+
+fn field_matches(
+    repo: &Repo,
+    field: Field,
+    pattern: &Pattern,
+    branch: &mut Option<String>,
+) -> bool {
+    match field {
+        Field::Host => {
+            pattern.matches(&repo.host) || repo.remotes.iter().any(|r| pattern.matches(&r.host))
+        }
+        Field::Owner => {
+            pattern.matches(&repo.owner) || repo.remotes.iter().any(|r| pattern.matches(&r.owner))
+        }
+        Field::Name => {
+            pattern.matches(&repo.repo) || repo.remotes.iter().any(|r| pattern.matches(&r.repo))
+        }
+        Field::Path => pattern.matches_path(&repo.full_path),
+        Field::Branch => pattern.matches(branch.get_or_insert_with(|| get_branch(&repo.full_path))),
+        Field::Tag => repo.tags.iter().any(|tag| pattern.matches(tag)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::context::database::models::RepoRemote;
+
+    fn repo(host: &str, owner: &str, name: &str) -> Repo {
+        Repo {
+            host: host.to_string(),
+            owner: owner.to_string(),
+            repo: name.to_string(),
+            full_path: format!("/work/{}/{}/{}", host, owner, name),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_qualifiers_scope_the_match() {
+        let api = repo("gitlab.com", "acme", "api");
+
+        assert!(Query::parse("host:gitlab.com").matches(&api));
+        assert!(Query::parse("owner:ACME name:api").matches(&api));
+        assert!(!Query::parse("owner:acme name:web").matches(&api));
+        assert!(Query::parse("host:*.com").matches(&api));
+        assert!(Query::parse("path:/work/gitlab.com").matches(&api));
+        assert!(!Query::parse("path:/work/gitlab").matches(&api), "paths match whole components");
+    }
+
+    #[test]
+    fn test_negation_excludes() {
+        let api = repo("github.com", "archived", "api");
+
+        assert!(!Query::parse("-owner:archived").matches(&api));
+        assert!(Query::parse("-owner:acme").matches(&api));
+        assert!(!Query::parse("api -api").matches(&api));
+    }
+
+    #[test]
+    fn test_keywords_and_qualifiers_combine() {
+        let api = repo("github.com", "acme", "api-server");
+
+        let query = Query::parse("server owner:acme");
+        assert_eq!(query.keywords(), "server");
+        assert_eq!(query.match_kind(&api), Some(MatchKind::RepoContains));
+
+        assert_eq!(Query::parse("owner:acme").match_kind(&api), Some(MatchKind::Filter));
+        // The weakest keyword match is reported, each keyword is kept for ranking.
+        let query = Query::parse("api-server acme");
+        assert_eq!(query.match_kind(&api), Some(MatchKind::PathContains));
+        assert_eq!(
+            query.keyword_kinds(&api),
+            Some(vec![("api-server", MatchKind::RepoExact), ("acme", MatchKind::PathContains)])
+        );
+    }
+
+    #[test]
+    fn test_tag_and_remote_qualifiers() {
+        let mut fork = repo("github.com", "me", "prog");
+        fork.tags = vec!["infra".to_string()];
+        fork.remotes = vec![RepoRemote::new("upstream", "https://github.com/bytemain/prog.git")];
+
+        assert!(Query::parse("tag:infra").matches(&fork));
+        assert!(Query::parse("tag:inf*").matches(&fork));
+        assert!(Query::parse("owner:bytemain").matches(&fork));
+        assert!(!Query::parse("-tag:infra").matches(&fork));
+    }
+
+    #[test]
+    fn test_unknown_qualifiers_are_keywords() {
+        let query = Query::parse("foo:bar owner:");
+        assert_eq!(query.keywords(), "foo:bar owner:");
+    }
+
+    #[test]
+    fn test_branch_qualifier_reads_head() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let git_dir = temp_dir.path().join(".git");
+        std::fs::create_dir_all(&git_dir).unwrap();
+        std::fs::write(git_dir.join("HEAD"), "ref: refs/heads/release/1.2\n").unwrap();
+        let mut repo = repo("github.com", "acme", "api");
+        repo.full_path = temp_dir.path().to_string_lossy().to_string();
+
+        assert!(Query::parse("branch:release/*").matches(&repo));
+        assert!(!Query::parse("branch:main").matches(&repo));
+    }
+}
@@ -0,0 +1,145 @@
+use super::path::expand_tilde;
+use globset::{Glob, GlobBuilder, GlobSet, GlobSetBuilder};
+use ignore::gitignore::Gitignore;
+use log::warn;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+/// Name of the per-directory file listing, gitignore style, the directories
+/// below it that are not searched for repositories.
+pub const IGNORE_FILE: &str = ".progignore";
+
+/// Repositories left out of the index: paths matching `exclude`, remotes on a
+/// host in `exclude_hosts` or of an owner in `exclude_owners`, and whatever a
+/// `.progignore` above them names.
+#[derive(Debug, Default)]
+pub struct Excludes {
+    paths: GlobSet,
+    hosts: GlobSet,
+    owners: GlobSet,
+    /// Parsed `.progignore` of each directory looked at, `None` when it has none.
+    ignore_files: Mutex<HashMap<PathBuf, Option<Gitignore>>>,
+}
+
+impl Excludes {
+    pub fn new(paths: &[String], hosts: &[String], owners: &[String]) -> Self {
+        let paths: Vec<String> = paths.iter().map(|pattern| expand_tilde(pattern)).collect();
+        Self {
+            paths: build_set(&paths, Glob::new),
+            hosts: build_set(hosts, |pattern| {
+                GlobBuilder::new(pattern).case_insensitive(true).build()
+            }),
+            owners: build_set(owners, |pattern| {
+                GlobBuilder::new(pattern).case_insensitive(true).build()
+            }),
+            ignore_files: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Whether `path` matches one of the `exclude` globs.
+    pub fn excludes_path(&self, path: &Path) -> bool {
+        self.paths.is_match(path)
+    }
+
+    /// Whether a remote on `host` owned by `owner` is denied.
+    pub fn excludes_remote(&self, host: &str, owner: &str) -> bool {
+        self.hosts.is_match(host) || (!owner.is_empty() && self.owners.is_match(owner))
+    }
+
+    /// Whether the repository at `path` with its remote on `host` owned by
+    /// `owner` is left out, by the config or by a `.progignore` above it.
+    pub fn excludes(&self, path: &str, host: &str, owner: &str) -> bool {
+        let path = Path::new(path);
+        self.excludes_path(path) || self.excludes_remote(host, owner) || self.is_ignored(path)
+    }
+
+    /// Whether a `.progignore` in a directory above `path` names it.
+    fn is_ignored(&self, path: &Path) -> bool {
+        let mut ignore_files = self.ignore_files.lock().unwrap_or_else(|e| e.into_inner());
+        path.ancestors().skip(1).any(|dir| {
+            let ignore_file = ignore_files.entry(dir.to_path_buf()).or_insert_with(|| {
+                let file = dir.join(IGNORE_FILE);
+                if !file.is_file() {
+                    return None;
+                }
+                let (gitignore, error) = Gitignore::new(&file);
+                if let Some(e) = error {
+                    warn!("Invalid pattern in {}: {}", file.display(), e);
+                }
+                Some(gitignore)
+            });
+            ignore_file.as_ref().is_some_and(|gitignore| {
+                gitignore.matched_path_or_any_parents(path, true).is_ignore()
+            })
+        })
+    }
+}
+
+fn build_set<F>(patterns: &[String], build: F) -> GlobSet
+where
+    F: Fn(&str) -> Result<Glob, globset::Error>,
+{
+    let mut builder = GlobSetBuilder::new();
+    for pattern in patterns {
+        match build(pattern) {
+            Ok(glob) => {
+                builder.add(glob);
+            }
+            Err(e) => warn!("Ignoring invalid exclude pattern '{}': {}", pattern, e),
+        }
+    }
+    builder.build().unwrap_or_else(|e| {
+        warn!("Ignoring exclude patterns: {}", e);
+        GlobSet::empty()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    fn patterns(patterns: &[&str]) -> Vec<String> {
+        patterns.iter().map(|pattern| pattern.to_string()).collect()
+    }
+
+    #[test]
+    fn test_excludes_by_path_host_and_owner() {
+        let excludes = Excludes::new(
+            &patterns(&["**/node_modules/**", "/work/fixtures/*"]),
+            &patterns(&["*.internal"]),
+            &patterns(&["archived-*"]),
+        );
+
+        assert!(excludes.excludes("/work/app/node_modules/left-pad", "github.com", "x"));
+        assert!(excludes.excludes("/work/fixtures/broken", "github.com", "acme"));
+        assert!(excludes.excludes("/work/github.com/acme/api", "git.Corp.internal", "acme"));
+        assert!(excludes.excludes(
+            "/work/github.com/archived-acme/api",
+            "github.com",
+            "Archived-Acme"
+        ));
+        assert!(!excludes.excludes("/work/github.com/acme/api", "github.com", "acme"));
+        assert!(!excludes.excludes("/work/scratch", "local", ""));
+    }
+
+    #[test]
+    fn test_progignore_applies_below_its_directory() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let base = temp_dir.path();
+        fs::create_dir_all(base.join("github.com")).unwrap();
+        fs::write(base.join(IGNORE_FILE), "vendor/\n").unwrap();
+        fs::write(base.join("github.com").join(IGNORE_FILE), "/acme/legacy-*\n").unwrap();
+
+        let excludes = Excludes::default();
+        let excluded = |path: &str| {
+            excludes.excludes(&base.join(path).to_string_lossy(), "github.com", "acme")
+        };
+        assert!(excluded("vendor/lib"));
+        assert!(excluded("github.com/acme/vendor/lib"));
+        assert!(excluded("github.com/acme/legacy-api"));
+        assert!(!excluded("github.com/acme/api"));
+        assert!(!excluded("legacy-api"));
+    }
+}
@@ -0,0 +1,176 @@
+use crate::helpers::colors::Colorize;
+
+const SCORE_MATCH: i64 = 16;
+const SCORE_GAP_START: i64 = -3;
+const SCORE_GAP_EXTENSION: i64 = -1;
+const BONUS_PATH_BOUNDARY: i64 = 10;
+const BONUS_BOUNDARY: i64 = 8;
+const BONUS_CAMEL_CASE: i64 = 7;
+const BONUS_CONSECUTIVE: i64 = 6;
+const BONUS_FIRST_CHAR: i64 = 4;
+
+/// A subsequence match of a pattern inside a text.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FuzzyMatch {
+    pub score: i64,
+    /// Char indices (not byte offsets) of the matched characters in the text.
+    pub positions: Vec<usize>,
+}
+
+/// Bonus for a match at `index`, depending on the character before it.
+fn boundary_bonus(text: &[char], index: usize) -> i64 {
+    let Some(&prev) = index.checked_sub(1).and_then(|i| text.get(i)) else {
+        return BONUS_PATH_BOUNDARY;
+    };
+    let current = text[index];
+    match prev {
+        '/' | '\\' => BONUS_PATH_BOUNDARY,
+        '-' | '_' | '.' | ' ' | ':' | '@' => BONUS_BOUNDARY,
+        _ if prev.is_lowercase() && current.is_uppercase() => BONUS_CAMEL_CASE,
+        _ if !prev.is_ascii_digit() && current.is_ascii_digit() => BONUS_CAMEL_CASE,
+        _ => 0,
+    }
+}
+
+fn gap_penalty(len: usize) -> i64 {
+    if len == 0 { 0 } else { SCORE_GAP_START + SCORE_GAP_EXTENSION * (len as i64 - 1) }
+}
+
+/// Scores `pattern` as a case-insensitive subsequence of `text`, fzf style:
+/// matches at the start of a path component, word or camelCase hump and runs
+/// of consecutive characters score higher, gaps lower. Returns the best
+/// alignment, or `None` if `pattern` is not a subsequence of `text`.
+pub fn fuzzy_match(text: &str, pattern: &str) -> Option<FuzzyMatch> {
+    let original: Vec<char> = text.chars().collect();
+    let haystack: Vec<char> =
+        original.iter().map(|c| c.to_lowercase().next().unwrap_or(*c)).collect();
+    let needle: Vec<char> = pattern.chars().map(|c| c.to_lowercase().next().unwrap_or(c)).collect();
+    if needle.is_empty() || needle.len() > haystack.len() {
+        return None;
+    }
+
+    // Cheap rejection before the quadratic search.
+    let mut rest = haystack.iter();
+    if !needle.iter().all(|n| rest.any(|h| h == n)) {
+        return None;
+    }
+
+    let (n, m) = (needle.len(), haystack.len());
+    // best[i][j]: best score with needle[..=i] matched and needle[i] at text[j].
+    let mut best = vec![vec![None::<i64>; m]; n];
+    let mut from = vec![vec![0usize; m]; n];
+
+    for j in 0..m {
+        if haystack[j] == needle[0] {
+            let bonus = boundary_bonus(&original, j);
+            best[0][j] = Some(SCORE_MATCH + bonus + bonus.min(BONUS_FIRST_CHAR));
+        }
+    }
+
+    for i in 1..n {
+        for j in i..m {
+            if haystack[j] != needle[i] {
+                continue;
+            }
+            let bonus = boundary_bonus(&original, j);
+            let mut candidate: Option<(i64, usize)> = None;
+            for (k, previous) in best[i - 1].iter().enumerate().take(j).skip(i - 1) {
+                let Some(previous) = *previous else {
+                    continue;
+                };
+                let score = if k + 1 == j {
+                    previous + bonus.max(BONUS_CONSECUTIVE)
+                } else {
+                    previous + bonus + gap_penalty(j - k - 1)
+                };
+                if candidate.is_none_or(|(best_score, _)| score > best_score) {
+                    candidate = Some((score, k));
+                }
+            }
+            if let Some((score, k)) = candidate {
+                best[i][j] = Some(score + SCORE_MATCH);
+                from[i][j] = k;
+            }
+        }
+    }
+
+    let (mut j, score) = best[n - 1]
+        .iter()
+        .enumerate()
+        .filter_map(|(j, score)| score.map(|score| (j, score)))
+        .max_by_key(|&(j, score)| (score, std::cmp::Reverse(j)))?;
+
+    let mut positions = vec![0; n];
+    for i in (0..n).rev() {
+        positions[i] = j;
+        j = from[i][j];
+    }
+
+    Some(FuzzyMatch { score, positions })
+}
+
+/// Colors the characters of `text` at the given char `positions`.
+pub fn highlight(text: &str, positions: &[usize]) -> String {
+    if positions.is_empty() {
+        return text.to_string();
+    }
+
+    let mut result = String::new();
+    let mut run = String::new();
+    for (i, c) in text.chars().enumerate() {
+        if positions.contains(&i) {
+            run.push(c);
+            continue;
+        }
+        if !run.is_empty() {
+            result.push_str(&std::mem::take(&mut run).yellow().to_string());
+        }
+        result.push(c);
+    }
+    if !run.is_empty() {
+        result.push_str(&run.yellow().to_string());
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fuzzy_match_requires_subsequence() {
+        assert!(fuzzy_match("prog-cli", "prgcli").is_some());
+        assert!(fuzzy_match("vscode", "vsc").is_some());
+        assert!(fuzzy_match("prog", "gorp").is_none());
+        assert!(fuzzy_match("prog", "").is_none());
+    }
+
+    #[test]
+    fn test_fuzzy_match_prefers_boundaries() {
+        let m = fuzzy_match("prog-cli", "pc").unwrap();
+        assert_eq!(m.positions, vec![0, 5]);
+
+        let m = fuzzy_match("bytemain/myProgCli", "pc").unwrap();
+        assert_eq!(m.positions, vec![11, 15], "camelCase humps beat inner letters");
+    }
+
+    #[test]
+    fn test_fuzzy_match_scores_consecutive_higher() {
+        let tight = fuzzy_match("vscode", "vsc").unwrap();
+        let loose = fuzzy_match("visual-studio-code", "vsc").unwrap();
+        let scattered = fuzzy_match("evasive-scan", "vsc").unwrap();
+        assert!(tight.score > scattered.score);
+        assert!(loose.score > scattered.score, "word starts beat scattered letters");
+    }
+
+    #[test]
+    fn test_fuzzy_match_is_case_insensitive() {
+        let m = fuzzy_match("VSCode", "vsc").unwrap();
+        assert_eq!(m.positions, vec![0, 1, 2]);
+    }
+
+    #[test]
+    fn test_highlight_without_positions_is_unchanged() {
+        assert_eq!(highlight("prog", &[]), "prog");
+    }
+}
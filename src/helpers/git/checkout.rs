@@ -0,0 +1,91 @@
+use anyhow::bail;
+use std::process::{Command, Stdio};
+
+/// Branch and tag names of the repository at `repo`. Remote-tracking branches
+/// are listed without their remote, the way `git switch` accepts them.
+pub fn ref_names(repo: &str) -> Vec<String> {
+    let output = Command::new("git")
+        .args(["for-each-ref", "--format=%(refname)", "refs/heads", "refs/tags", "refs/remotes"])
+        .current_dir(repo)
+        .stdin(Stdio::null())
+        .stderr(Stdio::null())
+        .output();
+    let Ok(output) = output else {
+        return Vec::new();
+    };
+
+    let mut names: Vec<String> = String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .filter_map(|line| {
+            let remote_branch =
+                || line.strip_prefix("refs/remotes/")?.split_once('/').map(|(_, name)| name);
+            let local =
+                line.strip_prefix("refs/heads/").or_else(|| line.strip_prefix("refs/tags/"));
+            local.or_else(remote_branch)
+        })
+        .filter(|name| *name != "HEAD")
+        .map(str::to_string)
+        .collect();
+    names.sort();
+    names.dedup();
+    names
+}
+
+/// Fetches `refspec` from `remote` into the repository at `repo`.
+pub fn fetch(repo: &str, remote: &str, refspec: &str) -> anyhow::Result<()> {
+    run_git(repo, &["fetch", "--quiet", remote, refspec])
+}
+
+/// Switches the repository at `repo` to `name`: a branch (created from the
+/// remote-tracking branch of that name if needed), or else a detached tag or commit.
+pub fn switch(repo: &str, name: &str) -> anyhow::Result<()> {
+    run_git(repo, &["switch", "--quiet", name])
+        .or_else(|_| run_git(repo, &["switch", "--quiet", "--detach", name]))
+}
+
+fn run_git(repo: &str, args: &[&str]) -> anyhow::Result<()> {
+    let output = Command::new("git").args(args).current_dir(repo).stdin(Stdio::null()).output()?;
+    if !output.status.success() {
+        bail!(
+            "git {} failed: {}",
+            args.first().unwrap_or(&""),
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ref_names_and_switch() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let repo = temp_dir.path().to_str().unwrap();
+        let git = |args: &[&str]| {
+            Command::new("git").args(args).current_dir(repo).output().unwrap();
+        };
+
+        git(&["init", "--initial-branch=main"]);
+        git(&[
+            "-c",
+            "user.email=a@b.c",
+            "-c",
+            "user.name=n",
+            "commit",
+            "--allow-empty",
+            "-m",
+            "init",
+        ]);
+        git(&["branch", "feature/x"]);
+        git(&["tag", "v1"]);
+
+        assert_eq!(ref_names(repo), vec!["feature/x", "main", "v1"]);
+
+        switch(repo, "feature/x").unwrap();
+        assert_eq!(crate::helpers::git::get_branch(repo), "feature/x");
+        switch(repo, "v1").unwrap();
+        assert!(switch(repo, "missing").is_err());
+    }
+}
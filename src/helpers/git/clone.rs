@@ -23,6 +23,28 @@ pub fn clone(
     Ok(())
 }
 
+/// Like [`clone`], but captures git's output and only returns it on failure.
+pub fn clone_quiet(url: &str, rest: &[String], target_path: &str) -> anyhow::Result<()> {
+    let output = Command::new("git")
+        .arg("clone")
+        .arg("--quiet")
+        .arg(url)
+        .arg(target_path)
+        .args(rest)
+        .stdin(Stdio::null())
+        .output()?;
+
+    if !output.status.success() {
+        bail!(
+            "git clone failed with exit status: {}\n{}",
+            output.status,
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -0,0 +1,464 @@
+use super::branch::resolve_gitdir;
+use globset::GlobBuilder;
+use log::warn;
+use std::fs;
+use std::iter::Peekable;
+use std::path::{Path, PathBuf};
+use std::str::Chars;
+
+/// How deep includes may nest, as in git.
+const MAX_INCLUDE_DEPTH: usize = 10;
+
+/// Entries of one or more git config files, in the order git reads them.
+///
+/// Keys are `section.name` or `section.subsection.name`, with the section and
+/// the name lowercased and the subsection kept as written.
+#[derive(Debug, Default)]
+pub struct GitConfig {
+    entries: Vec<(String, String)>,
+}
+
+/// What `includeIf` conditions are evaluated against.
+#[derive(Debug, Default)]
+struct Repository {
+    gitdir: Option<PathBuf>,
+    branch: Option<String>,
+}
+
+impl GitConfig {
+    /// Parses the text of one config file, without following its includes.
+    pub fn parse(text: &str) -> Self {
+        Self { entries: parse_entries(text) }
+    }
+
+    /// Every `key` with its value, in file order.
+    pub fn entries(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.entries.iter().map(|(key, value)| (key.as_str(), value.as_str()))
+    }
+
+    /// The last value of `key`, which is the one that wins.
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.entries().filter(|(k, _)| *k == key).map(|(_, value)| value).last()
+    }
+
+    pub fn get_bool(&self, key: &str) -> bool {
+        self.get(key).is_some_and(is_truthy)
+    }
+
+    /// Applies the `url.<base>.insteadOf` rule with the longest matching
+    /// prefix, as git does for fetch URLs.
+    pub fn rewrite_url(&self, url: &str) -> String {
+        let rule = self
+            .entries()
+            .filter_map(|(key, prefix)| {
+                let base = key.strip_prefix("url.")?.strip_suffix(".insteadof")?;
+                url.starts_with(prefix).then_some((base, prefix))
+            })
+            .max_by_key(|(_, prefix)| prefix.len());
+        match rule {
+            Some((base, prefix)) => format!("{}{}", base, &url[prefix.len()..]),
+            None => url.to_string(),
+        }
+    }
+
+    fn read_file(&mut self, path: &Path, repository: &Repository, depth: usize) {
+        if let Ok(text) = fs::read_to_string(path) {
+            self.read_text(&text, path.parent(), repository, depth);
+        }
+    }
+
+    /// Adds the entries of `text`, with the files it includes in place. Relative
+    /// include paths are resolved against `dir`, the directory of the file.
+    fn read_text(&mut self, text: &str, dir: Option<&Path>, repository: &Repository, depth: usize) {
+        for (key, value) in parse_entries(text) {
+            let include = match key.strip_prefix("includeif.") {
+                Some(rest) => rest
+                    .strip_suffix(".path")
+                    .is_some_and(|condition| include_applies(condition, dir, repository)),
+                None => key == "include.path",
+            };
+            let included = include.then(|| include_path(&value, dir)).flatten();
+            self.entries.push((key, value));
+
+            if let Some(path) = included {
+                if depth == 0 {
+                    warn!("Includes nested too deeply at {}", path.display());
+                    continue;
+                }
+                self.read_file(&path, repository, depth - 1);
+            }
+        }
+    }
+}
+
+/// The config git uses in the repository at `repo`: the system and global
+/// files, then the repository's own, then the worktree's own when enabled.
+pub fn read_repo_config(repo: &str) -> GitConfig {
+    let mut config = GitConfig::default();
+    let gitdir = resolve_gitdir(repo);
+    let repository =
+        Repository { branch: gitdir.as_deref().and_then(read_head_branch), gitdir: gitdir.clone() };
+
+    for path in user_config_files() {
+        config.read_file(&path, &repository, MAX_INCLUDE_DEPTH);
+    }
+    let Some(gitdir) = gitdir else {
+        return config;
+    };
+
+    // A linked worktree shares the config of the repository it belongs to.
+    let common_dir = match fs::read_to_string(gitdir.join("commondir")) {
+        Ok(common_dir) => gitdir.join(common_dir.trim()),
+        Err(_) => gitdir.clone(),
+    };
+    config.read_file(&common_dir.join("config"), &repository, MAX_INCLUDE_DEPTH);
+    if config.get_bool("extensions.worktreeconfig") {
+        config.read_file(&gitdir.join("config.worktree"), &repository, MAX_INCLUDE_DEPTH);
+    }
+    config
+}
+
+/// The system and global config files, in the order git reads them.
+fn user_config_files() -> Vec<PathBuf> {
+    let mut files = Vec::new();
+    if !std::env::var("GIT_CONFIG_NOSYSTEM").is_ok_and(|value| is_truthy(&value)) {
+        let system = std::env::var_os("GIT_CONFIG_SYSTEM").unwrap_or("/etc/gitconfig".into());
+        files.push(PathBuf::from(system));
+    }
+    if let Some(global) = std::env::var_os("GIT_CONFIG_GLOBAL") {
+        files.push(PathBuf::from(global));
+        return files;
+    }
+    let xdg_config = std::env::var_os("XDG_CONFIG_HOME")
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| dirs::home_dir().map(|home| home.join(".config")));
+    if let Some(xdg_config) = xdg_config {
+        files.push(xdg_config.join("git").join("config"));
+    }
+    if let Some(home) = dirs::home_dir() {
+        files.push(home.join(".gitconfig"));
+    }
+    files
+}
+
+fn is_truthy(value: &str) -> bool {
+    matches!(value.to_lowercase().as_str(), "true" | "yes" | "on" | "1")
+}
+
+fn read_head_branch(gitdir: &Path) -> Option<String> {
+    let head = fs::read_to_string(gitdir.join("HEAD")).ok()?;
+    Some(head.trim().strip_prefix("ref:")?.trim().strip_prefix("refs/heads/")?.to_string())
+}
+
+fn include_path(value: &str, dir: Option<&Path>) -> Option<PathBuf> {
+    if let Some(rest) = value.strip_prefix("~/") {
+        return dirs::home_dir().map(|home| home.join(rest));
+    }
+    let path = PathBuf::from(value);
+    if path.is_absolute() {
+        return Some(path);
+    }
+    dir.map(|dir| dir.join(path))
+}
+
+/// Whether the `includeIf` `condition` holds. `hasconfig:` and unknown
+/// conditions never do.
+fn include_applies(condition: &str, dir: Option<&Path>, repository: &Repository) -> bool {
+    if let Some(pattern) = condition.strip_prefix("gitdir:") {
+        return gitdir_matches(pattern, dir, repository, false);
+    }
+    if let Some(pattern) = condition.strip_prefix("gitdir/i:") {
+        return gitdir_matches(pattern, dir, repository, true);
+    }
+    if let Some(pattern) = condition.strip_prefix("onbranch:") {
+        let pattern =
+            if pattern.ends_with('/') { format!("{}**", pattern) } else { pattern.into() };
+        return repository
+            .branch
+            .as_deref()
+            .is_some_and(|branch| glob_matches(&pattern, Path::new(branch), false));
+    }
+    false
+}
+
+fn gitdir_matches(
+    pattern: &str,
+    dir: Option<&Path>,
+    repository: &Repository,
+    case_insensitive: bool,
+) -> bool {
+    let Some(gitdir) = &repository.gitdir else {
+        return false;
+    };
+
+    let mut pattern = if let Some(rest) = pattern.strip_prefix("~/") {
+        match dirs::home_dir() {
+            Some(home) => format!("{}/{}", home.display(), rest),
+            None => return false,
+        }
+    } else if let Some(rest) = pattern.strip_prefix("./") {
+        match dir {
+            Some(dir) => format!("{}/{}", dir.display(), rest),
+            None => return false,
+        }
+    } else if pattern.starts_with('/') {
+        pattern.to_string()
+    } else {
+        format!("**/{}", pattern)
+    };
+    if pattern.ends_with('/') {
+        pattern.push_str("**");
+    }
+
+    glob_matches(&pattern, gitdir, case_insensitive)
+        || gitdir.canonicalize().is_ok_and(|real| glob_matches(&pattern, &real, case_insensitive))
+}
+
+fn glob_matches(pattern: &str, path: &Path, case_insensitive: bool) -> bool {
+    match GlobBuilder::new(pattern)
+        .literal_separator(true)
+        .case_insensitive(case_insensitive)
+        .build()
+    {
+        Ok(glob) => glob.compile_matcher().is_match(path),
+        Err(e) => {
+            warn!("Invalid includeIf pattern '{}': {}", pattern, e);
+            false
+        }
+    }
+}
+
+/// Parses config file syntax into `(key, value)` pairs. A key without a
+/// value is a boolean `true`.
+fn parse_entries(text: &str) -> Vec<(String, String)> {
+    let mut entries = Vec::new();
+    let mut section = String::new();
+    let mut chars = text.chars().peekable();
+
+    while let Some(&c) = chars.peek() {
+        match c {
+            _ if c.is_whitespace() => {
+                chars.next();
+            }
+            '#' | ';' => skip_line(&mut chars),
+            '[' => {
+                chars.next();
+                match parse_section(&mut chars) {
+                    Some(name) => section = name,
+                    None => {
+                        // A broken header; skip what follows until the next one.
+                        section.clear();
+                        skip_line(&mut chars);
+                    }
+                }
+            }
+            _ => {
+                let mut name = String::new();
+                while let Some(&c) = chars.peek() {
+                    if !(c.is_ascii_alphanumeric() || c == '-') {
+                        break;
+                    }
+                    name.push(c.to_ascii_lowercase());
+                    chars.next();
+                }
+                if name.is_empty() || section.is_empty() {
+                    skip_line(&mut chars);
+                    continue;
+                }
+                while chars.next_if(|c| *c == ' ' || *c == '\t').is_some() {}
+
+                let value = if chars.next_if_eq(&'=').is_some() {
+                    parse_value(&mut chars)
+                } else {
+                    skip_line(&mut chars);
+                    String::from("true")
+                };
+                entries.push((format!("{}.{}", section, name), value));
+            }
+        }
+    }
+    entries
+}
+
+fn skip_line(chars: &mut Peekable<Chars>) {
+    for c in chars.by_ref() {
+        if c == '\n' {
+            break;
+        }
+    }
+}
+
+/// Parses a section header after its `[`: `[section "subsection"]`, or the
+/// older `[section.subsection]`, which is not case-sensitive.
+fn parse_section(chars: &mut Peekable<Chars>) -> Option<String> {
+    let mut name = String::new();
+    while let Some(c) = chars.next_if(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '.')) {
+        name.push(c.to_ascii_lowercase());
+    }
+    if name.is_empty() {
+        return None;
+    }
+    while chars.next_if(|c| *c == ' ' || *c == '\t').is_some() {}
+
+    match chars.next()? {
+        ']' => Some(name),
+        '"' => {
+            let mut subsection = String::new();
+            loop {
+                match chars.next()? {
+                    '"' => break,
+                    '\\' => subsection.push(chars.next()?),
+                    '\n' => return None,
+                    c => subsection.push(c),
+                }
+            }
+            (chars.next()? == ']').then(|| format!("{}.{}", name, subsection))
+        }
+        _ => None,
+    }
+}
+
+/// Parses a value after its `=`, up to the end of the line: quotes are
+/// dropped, escapes resolved, a `\` at the end of a line continues it, and
+/// whitespace around the value is trimmed.
+fn parse_value(chars: &mut Peekable<Chars>) -> String {
+    let mut value = String::new();
+    let mut spaces = String::new();
+    let mut quoted = false;
+    while chars.next_if(|c| *c == ' ' || *c == '\t').is_some() {}
+
+    while let Some(c) = chars.next() {
+        match c {
+            '\n' => break,
+            '\r' => {}
+            '#' | ';' if !quoted => {
+                skip_line(chars);
+                break;
+            }
+            ' ' | '\t' if !quoted => spaces.push(c),
+            '"' => {
+                value.push_str(&spaces);
+                spaces.clear();
+                quoted = !quoted;
+            }
+            '\\' => {
+                let escaped = match chars.next() {
+                    Some('\n') => continue,
+                    Some('\r') => {
+                        chars.next_if_eq(&'\n');
+                        continue;
+                    }
+                    Some('n') => '\n',
+                    Some('t') => '\t',
+                    Some('b') => '\u{8}',
+                    Some(c) => c,
+                    None => break,
+                };
+                value.push_str(&spaces);
+                spaces.clear();
+                value.push(escaped);
+            }
+            c => {
+                value.push_str(&spaces);
+                spaces.clear();
+                value.push(c);
+            }
+        }
+    }
+    value
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::process::Command;
+
+    #[test]
+    fn test_parse_syntax() {
+        let config = GitConfig::parse(
+            "# comment\n\
+             [Core]\n\
+             \tbare = false ; trailing comment\n\
+             \tIgnoreCase\n\
+             [remote \"My.Fork\"]\n\
+             \turl = \"https://example.com/a b\" # quoted\n\
+             [branch.Main] remote = origin\n\
+             [alias]\n\
+             \tlg = log --graph \\\n\
+             \t  --oneline\n\
+             \tsay = \"echo \\\"hi\\\"\\tthere\"\n",
+        );
+
+        assert_eq!(config.get("core.bare"), Some("false"));
+        assert!(config.get_bool("core.ignorecase"));
+        assert_eq!(config.get("remote.My.Fork.url"), Some("https://example.com/a b"));
+        assert_eq!(config.get("branch.main.remote"), Some("origin"));
+        assert_eq!(config.get("alias.lg"), Some("log --graph \t  --oneline"));
+        assert_eq!(config.get("alias.say"), Some("echo \"hi\"\tthere"));
+    }
+
+    #[test]
+    fn test_rewrite_url_uses_longest_prefix() {
+        let config = GitConfig::parse(
+            "[url \"git@github.com:\"]\n\
+             \tinsteadOf = https://github.com/\n\
+             [url \"git@github.com:acme-mirror/\"]\n\
+             \tinsteadOf = https://github.com/acme/\n\
+             [url \"https://gitlab.com/\"]\n\
+             \tinsteadOf = gl:\n",
+        );
+
+        assert_eq!(config.rewrite_url("https://github.com/me/prog"), "git@github.com:me/prog");
+        assert_eq!(
+            config.rewrite_url("https://github.com/acme/api"),
+            "git@github.com:acme-mirror/api"
+        );
+        assert_eq!(config.rewrite_url("gl:group/app"), "https://gitlab.com/group/app");
+        assert_eq!(config.rewrite_url("https://example.com/x"), "https://example.com/x");
+    }
+
+    #[test]
+    fn test_read_repo_config_follows_includes_and_worktrees() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let root = temp_dir.path().canonicalize().unwrap();
+        let repo = root.join("work").join("api");
+        fs::create_dir_all(&repo).unwrap();
+        let git = |args: &[&str]| {
+            Command::new("git").args(args).current_dir(&repo).output().unwrap();
+        };
+        git(&["init", "--initial-branch=main"]);
+        git(&["remote", "add", "origin", "https://github.com/acme/api.git"]);
+        git(&["-c", "user.email=a@b.c", "-c", "user.name=n", "commit", "--allow-empty", "-m", "i"]);
+        git(&["worktree", "add", "-b", "fix", "../api@fix"]);
+
+        fs::write(
+            root.join("work.gitconfig"),
+            "[url \"git@github.com:\"]\n\tinsteadOf = https://github.com/\n",
+        )
+        .unwrap();
+        fs::write(root.join("main.gitconfig"), "[user]\n\tname = On Main\n").unwrap();
+        let config_path = repo.join(".git").join("config");
+        let mut local = fs::read_to_string(&config_path).unwrap();
+        local.push_str(&format!(
+            "[includeIf \"gitdir:{}/\"]\n\tpath = ../../../work.gitconfig\n\
+             [includeIf \"gitdir:/elsewhere/\"]\n\tpath = /nonexistent\n\
+             [includeIf \"onbranch:main\"]\n\tpath = {}\n",
+            root.join("work").display(),
+            root.join("main.gitconfig").display()
+        ));
+        fs::write(&config_path, local).unwrap();
+
+        let config = read_repo_config(&repo.to_string_lossy());
+        assert_eq!(config.get("remote.origin.url"), Some("https://github.com/acme/api.git"));
+        assert_eq!(
+            config.rewrite_url("https://github.com/acme/api.git"),
+            "git@github.com:acme/api.git"
+        );
+        assert_eq!(config.get("user.name"), Some("On Main"));
+
+        let worktree = read_repo_config(&root.join("work").join("api@fix").to_string_lossy());
+        assert_eq!(worktree.get("remote.origin.url"), Some("https://github.com/acme/api.git"));
+        assert_ne!(worktree.get("user.name"), Some("On Main"));
+    }
+}
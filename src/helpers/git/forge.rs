@@ -0,0 +1,259 @@
+/// A web page of a repository on a forge (GitHub, GitLab, Bitbucket, Gitea),
+/// as opposed to its clone URL.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ForgeUrl {
+    pub host: String,
+    /// May span several segments (GitLab subgroups).
+    pub owner: String,
+    pub repo: String,
+    pub target: ForgeTarget,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ForgeTarget {
+    /// A `tree`, `blob` or `src` page: a ref followed by a path. Branch names
+    /// may contain `/`, so the two are only told apart against a local clone.
+    Tree(Vec<String>),
+    /// A pull or merge request, with the refspec its head can be fetched from
+    /// when the forge publishes one.
+    PullRequest { number: u64, refspec: Option<String> },
+    /// Any other page (issues, commits, ...), which only names the repository.
+    Page,
+}
+
+/// Parses forge web URLs such as `https://github.com/acme/api/pull/42`,
+/// `.../tree/feature-x/src/lib`, `.../blob/main/README.md#L10`, GitLab's
+/// `.../-/tree/main` and Bitbucket's `.../src/main/lib`. Returns `None` for
+/// anything that is not such a page, including plain clone URLs, and for
+/// paths with `.` or `..` segments, which could lead out of the repository.
+pub fn parse_forge_url(input: &str) -> Option<ForgeUrl> {
+    let ForgePage { host, owner, repo, kind, rest } = split_forge_page(input)?;
+
+    let number = || rest.first().and_then(|n| n.parse::<u64>().ok());
+    let target = match kind.as_str() {
+        "tree" | "blob" => ForgeTarget::Tree(owned(&rest)),
+        // Gitea spells the kind of ref out: `src/branch/main/...`.
+        "src" => match rest.first() {
+            Some(&"branch" | &"tag" | &"commit") => ForgeTarget::Tree(owned(&rest[1..])),
+            _ => ForgeTarget::Tree(owned(&rest)),
+        },
+        "pull" | "pulls" => {
+            let number = number()?;
+            ForgeTarget::PullRequest { number, refspec: Some(format!("pull/{}/head", number)) }
+        }
+        "merge_requests" => {
+            let number = number()?;
+            ForgeTarget::PullRequest {
+                number,
+                refspec: Some(format!("merge-requests/{}/head", number)),
+            }
+        }
+        "pull-requests" => ForgeTarget::PullRequest { number: number()?, refspec: None },
+        _ => ForgeTarget::Page,
+    };
+
+    Some(ForgeUrl { host, owner, repo, target })
+}
+
+/// The clone URL of the repository a forge page such as `.../tree/main` or
+/// `.../-/merge_requests` belongs to, or `None` when `input` is not a page.
+pub fn forge_clone_url(input: &str) -> Option<String> {
+    let page = split_forge_page(input)?;
+    let scheme = if input.trim().starts_with("http://") { "http" } else { "https" };
+    Some(format!("{}://{}/{}/{}.git", scheme, page.host, page.owner, page.repo))
+}
+
+/// A forge page URL taken apart: `https://<host>/<owner>/<repo>/<kind>/<rest>`.
+struct ForgePage<'a> {
+    host: String,
+    owner: String,
+    repo: String,
+    kind: String,
+    rest: Vec<&'a str>,
+}
+
+fn split_forge_page(input: &str) -> Option<ForgePage<'_>> {
+    let rest = input.trim();
+    let rest = rest.strip_prefix("https://").or_else(|| rest.strip_prefix("http://"))?;
+    let rest = rest.split(['#', '?']).next().unwrap_or_default();
+    let mut segments = rest.split('/').filter(|s| !s.is_empty());
+    let host = segments.next()?.to_string();
+    let segments: Vec<&str> = segments.collect();
+    if segments.iter().any(|s| *s == "." || *s == "..") {
+        return None;
+    }
+
+    let (owner, repo, kind, rest) = if segments.len() >= 3 && is_page_kind(segments[2]) {
+        (segments[0].to_string(), segments[1], segments[2], &segments[3..])
+    } else {
+        // GitLab separates the project path, which may contain subgroups, from
+        // the page with `/-/`.
+        let dash = segments.iter().position(|s| *s == "-").filter(|&dash| dash >= 2)?;
+        (
+            segments[..dash - 1].join("/"),
+            segments[dash - 1],
+            *segments.get(dash + 1)?,
+            &segments[(dash + 2).min(segments.len())..],
+        )
+    };
+    if !is_page_kind(kind) {
+        return None;
+    }
+
+    let repo = repo.strip_suffix(".git").unwrap_or(repo).to_string();
+    Some(ForgePage { host, owner, repo, kind: kind.to_string(), rest: rest.to_vec() })
+}
+
+/// Path segments that follow `owner/repo` on a forge page.
+fn is_page_kind(segment: &str) -> bool {
+    matches!(
+        segment,
+        "tree"
+            | "blob"
+            | "src"
+            | "pull"
+            | "pulls"
+            | "merge_requests"
+            | "pull-requests"
+            | "issues"
+            | "commit"
+            | "commits"
+            | "compare"
+            | "releases"
+            | "tags"
+            | "branches"
+            | "actions"
+            | "pipelines"
+            | "wiki"
+    )
+}
+
+fn owned(segments: &[&str]) -> Vec<String> {
+    segments.iter().map(|s| s.to_string()).collect()
+}
+
+/// Splits the segments of a [`ForgeTarget::Tree`] into a ref and a path,
+/// preferring the longest ref that is one of `refs`. Without a known ref the
+/// first segment is taken, which also covers commit hashes.
+pub fn split_ref_path(segments: &[String], refs: &[String]) -> Option<(String, String)> {
+    if segments.is_empty() {
+        return None;
+    }
+    let split = (1..=segments.len())
+        .rev()
+        .find(|&i| refs.iter().any(|r| *r == segments[..i].join("/")))
+        .unwrap_or(1);
+    Some((segments[..split].join("/"), segments[split..].join("/")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tree(segments: &[&str]) -> ForgeTarget {
+        ForgeTarget::Tree(owned(segments))
+    }
+
+    #[test]
+    fn test_parse_github_urls() {
+        let url = parse_forge_url("https://github.com/acme/api/pull/42/files").unwrap();
+        assert_eq!(
+            (url.host.as_str(), url.owner.as_str(), url.repo.as_str()),
+            ("github.com", "acme", "api")
+        );
+        assert_eq!(
+            url.target,
+            ForgeTarget::PullRequest { number: 42, refspec: Some("pull/42/head".to_string()) }
+        );
+
+        let url = parse_forge_url("https://github.com/acme/api/tree/feature-x/src/lib").unwrap();
+        assert_eq!(url.target, tree(&["feature-x", "src", "lib"]));
+
+        let url = parse_forge_url("https://github.com/acme/api/blob/main/README.md#L10").unwrap();
+        assert_eq!(url.target, tree(&["main", "README.md"]));
+
+        let url = parse_forge_url("https://github.com/acme/api/issues/7?q=1").unwrap();
+        assert_eq!(url.target, ForgeTarget::Page);
+
+        let url = parse_forge_url("https://github.com/acme/api/tree/main/-/x").unwrap();
+        assert_eq!((url.owner.as_str(), url.repo.as_str()), ("acme", "api"));
+        assert_eq!(url.target, tree(&["main", "-", "x"]));
+    }
+
+    #[test]
+    fn test_parse_gitlab_and_bitbucket_urls() {
+        let url = parse_forge_url("https://gitlab.com/group/sub/api/-/tree/main/src").unwrap();
+        assert_eq!((url.owner.as_str(), url.repo.as_str()), ("group/sub", "api"));
+        assert_eq!(url.target, tree(&["main", "src"]));
+
+        let url = parse_forge_url("https://gitlab.com/group/api/-/merge_requests/3").unwrap();
+        assert_eq!(
+            url.target,
+            ForgeTarget::PullRequest {
+                number: 3,
+                refspec: Some("merge-requests/3/head".to_string())
+            }
+        );
+
+        let url = parse_forge_url("https://bitbucket.org/acme/api/src/main/lib/").unwrap();
+        assert_eq!(url.target, tree(&["main", "lib"]));
+        let url = parse_forge_url("https://bitbucket.org/acme/api/pull-requests/12").unwrap();
+        assert_eq!(url.target, ForgeTarget::PullRequest { number: 12, refspec: None });
+
+        let url = parse_forge_url("https://gitea.example.com/acme/api/src/branch/dev/cmd").unwrap();
+        assert_eq!(url.target, tree(&["dev", "cmd"]));
+    }
+
+    #[test]
+    fn test_parse_rejects_clone_urls() {
+        assert_eq!(parse_forge_url("https://github.com/acme/api.git"), None);
+        assert_eq!(parse_forge_url("https://github.com/acme/api"), None);
+        assert_eq!(parse_forge_url("git@github.com:acme/api.git"), None);
+        assert_eq!(parse_forge_url("https://github.com/acme/api/pull/abc"), None);
+        assert_eq!(parse_forge_url("api"), None);
+    }
+
+    #[test]
+    fn test_forge_clone_url() {
+        assert_eq!(
+            forge_clone_url("https://github.com/acme/api/tree/main/src").as_deref(),
+            Some("https://github.com/acme/api.git")
+        );
+        assert_eq!(
+            forge_clone_url("https://github.com/acme/api/pulls").as_deref(),
+            Some("https://github.com/acme/api.git")
+        );
+        assert_eq!(
+            forge_clone_url("http://gitlab.local/group/sub/api/-/merge_requests/3").as_deref(),
+            Some("http://gitlab.local/group/sub/api.git")
+        );
+        assert_eq!(forge_clone_url("https://github.com/acme/api.git"), None);
+        assert_eq!(forge_clone_url("https://gitlab.com/group/sub/api"), None);
+    }
+
+    #[test]
+    fn test_parse_rejects_dot_segments() {
+        assert_eq!(parse_forge_url("https://github.com/acme/api/tree/main/../../../../etc"), None);
+        assert_eq!(parse_forge_url("https://github.com/acme/api/blob/main/./src/lib.rs"), None);
+        assert_eq!(parse_forge_url("https://gitlab.com/group/../api/-/tree/main"), None);
+    }
+
+    #[test]
+    fn test_split_ref_path() {
+        let segments = owned(&["feature", "x", "src", "lib"]);
+        let refs = vec!["main".to_string(), "feature/x".to_string()];
+        assert_eq!(
+            split_ref_path(&segments, &refs),
+            Some(("feature/x".to_string(), "src/lib".to_string()))
+        );
+        assert_eq!(
+            split_ref_path(&segments, &[]),
+            Some(("feature".to_string(), "x/src/lib".to_string()))
+        );
+        assert_eq!(
+            split_ref_path(&owned(&["main"]), &refs),
+            Some(("main".to_string(), String::new()))
+        );
+        assert_eq!(split_ref_path(&[], &refs), None);
+    }
+}
@@ -0,0 +1,49 @@
+use std::process::{Command, Stdio};
+
+/// Returns the last commit of the repository at `repo` as
+/// `<short hash> <subject> (<relative date>)`.
+pub fn last_commit(repo: &str) -> Option<String> {
+    let output = Command::new("git")
+        .args(["log", "-1", "--format=%h %s (%cr)"])
+        .current_dir(repo)
+        .stdin(Stdio::null())
+        .stderr(Stdio::null())
+        .output()
+        .ok()?;
+
+    if !output.status.success() {
+        return None;
+    }
+    let line = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    if line.is_empty() { None } else { Some(line) }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_last_commit() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let repo = temp_dir.path().to_str().unwrap();
+        let git = |args: &[&str]| {
+            Command::new("git").args(args).current_dir(repo).output().unwrap();
+        };
+
+        git(&["init"]);
+        assert_eq!(last_commit(repo), None, "a repository without commits has no last commit");
+
+        git(&[
+            "-c",
+            "user.email=a@b.c",
+            "-c",
+            "user.name=n",
+            "commit",
+            "--allow-empty",
+            "-m",
+            "init",
+        ]);
+        let commit = last_commit(repo).unwrap();
+        assert!(commit.contains(" init ("), "unexpected last commit: {}", commit);
+    }
+}
@@ -1,9 +1,22 @@
 mod branch;
+mod checkout;
 mod clone;
+mod config;
+mod forge;
+mod log;
 mod status;
+mod submodule;
 mod url;
+mod worktree;
 
 pub use branch::get_branch;
-pub use clone::clone;
+pub use checkout::{fetch, ref_names, switch};
+pub use clone::{clone, clone_quiet};
+pub use forge::{ForgeTarget, ForgeUrl, forge_clone_url, parse_forge_url, split_ref_path};
+pub use log::last_commit;
 pub use status::{RepoStatus, get_repo_status};
-pub use url::{get_remote_url, parse_git_url, remote_url_is_valid};
+pub use submodule::{Submodule, get_submodules, unpushed_commits};
+pub use url::{get_remotes, parse_git_url, remote_url_is_valid};
+pub use worktree::{
+    add_worktree, has_worktrees, main_worktree, prune_worktrees, repair_worktrees, worktree_path,
+};
@@ -0,0 +1,117 @@
+use super::config::GitConfig;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+use std::process::{Command, Stdio};
+
+/// A submodule declared in the `.gitmodules` of a repository.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+pub struct Submodule {
+    pub name: String,
+    /// Path relative to the superproject, with `/` separators.
+    pub path: String,
+    #[serde(default)]
+    pub url: String,
+}
+
+impl Submodule {
+    pub fn full_path(&self, superproject: &str) -> String {
+        Path::new(superproject).join(&self.path).to_string_lossy().to_string()
+    }
+
+    /// Whether the submodule is checked out in `superproject`.
+    pub fn is_initialized(&self, superproject: &str) -> bool {
+        Path::new(&self.full_path(superproject)).join(".git").exists()
+    }
+}
+
+/// Lists the submodules declared in the `.gitmodules` of the repository at
+/// `repo`, in the order they are declared.
+pub fn get_submodules(repo: &str) -> Vec<Submodule> {
+    match fs::read_to_string(Path::new(repo).join(".gitmodules")) {
+        Ok(text) => submodules_from_config(&GitConfig::parse(&text)),
+        Err(_) => Vec::new(),
+    }
+}
+
+/// Submodule names may themselves contain dots and slashes.
+fn submodules_from_config(config: &GitConfig) -> Vec<Submodule> {
+    let mut submodules: Vec<Submodule> = Vec::new();
+    for (key, value) in config.entries() {
+        let Some(key) = key.strip_prefix("submodule.") else {
+            continue;
+        };
+        let (name, field) = match (key.strip_suffix(".path"), key.strip_suffix(".url")) {
+            (Some(name), _) => (name, "path"),
+            (_, Some(name)) => (name, "url"),
+            _ => continue,
+        };
+
+        let index = match submodules.iter().position(|s| s.name == name) {
+            Some(index) => index,
+            None => {
+                submodules.push(Submodule { name: name.to_string(), ..Default::default() });
+                submodules.len() - 1
+            }
+        };
+        let value = value.trim().to_string();
+        if field == "path" {
+            submodules[index].path = value;
+        } else {
+            submodules[index].url = value;
+        }
+    }
+    submodules.retain(|submodule| !submodule.path.is_empty());
+    submodules
+}
+
+/// Number of commits reachable from `HEAD` of the repository at `repo` that
+/// are on no remote-tracking branch, i.e. were never pushed anywhere.
+pub fn unpushed_commits(repo: &str) -> Option<u32> {
+    let output = Command::new("git")
+        .args(["rev-list", "--count", "HEAD", "--not", "--remotes"])
+        .current_dir(repo)
+        .stdin(Stdio::null())
+        .stderr(Stdio::null())
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    String::from_utf8_lossy(&output.stdout).trim().parse().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_submodules_from_config() {
+        let config = GitConfig::parse(
+            "[submodule \"vendor/lib\"]\n\
+             \tpath = vendor/lib\n\
+             \turl = ../lib\n\
+             [submodule \"docs.theme\"]\n\
+             \tpath = docs/theme\n\
+             \turl = https://github.com/acme/theme.git\n\
+             [submodule \"orphan\"]\n\
+             \turl = https://example.com/orphan.git\n",
+        );
+
+        assert_eq!(
+            submodules_from_config(&config),
+            vec![
+                Submodule {
+                    name: "vendor/lib".to_string(),
+                    path: "vendor/lib".to_string(),
+                    url: "../lib".to_string(),
+                },
+                Submodule {
+                    name: "docs.theme".to_string(),
+                    path: "docs/theme".to_string(),
+                    url: "https://github.com/acme/theme.git".to_string(),
+                },
+            ]
+        );
+    }
+}
@@ -1,4 +1,5 @@
-use std::process::Command;
+use super::config::{GitConfig, read_repo_config};
+use super::forge::forge_clone_url;
 
 #[derive(Debug, Clone)]
 pub struct ParsedGitUrl {
@@ -12,11 +13,27 @@ fn strip_git_suffix(name: &str) -> String {
     name.strip_suffix(".git").unwrap_or(name).to_string()
 }
 
+/// Splits `owner/repo` at its last `/`, so that an owner with subgroups
+/// (`group/sub/repo`) keeps all of them. `owner/` gives an empty name.
+fn split_owner_name(path: &str) -> Option<(String, String)> {
+    let trimmed = path.trim_end_matches('/');
+    let (owner, name) = match trimmed.rsplit_once('/') {
+        Some(split) => split,
+        None if trimmed.len() < path.len() => (trimmed, ""),
+        None => return None,
+    };
+    Some((owner.to_string(), strip_git_suffix(name)))
+}
+
 pub fn parse_git_url(input: &str) -> Option<ParsedGitUrl> {
     let s = input.trim();
     if s.is_empty() {
         return None;
     }
+    // Whatever follows `owner/repo` on a forge page is not part of the owner.
+    if let Some(clone_url) = forge_clone_url(s) {
+        return parse_git_url(&clone_url);
+    }
 
     // Helper to build ParsedGitUrl
     let build = |host: Option<String>, owner: Option<String>, name: String| -> ParsedGitUrl {
@@ -27,6 +44,17 @@ pub fn parse_git_url(input: &str) -> Option<ParsedGitUrl> {
         ParsedGitUrl { host, owner, name, fullname }
     };
 
+    // Handle local file URLs: file:///path/to/owner/repo(.git)
+    // The last two path segments are used as owner and repo name.
+    if let Some(rest) = s.strip_prefix("file://") {
+        let (authority, path) = rest.split_at(rest.find('/').unwrap_or(rest.len()));
+        let host = if authority.is_empty() { "localhost" } else { authority };
+        let mut parts = path.trim_end_matches('/').rsplit('/');
+        let name = parts.next().map(strip_git_suffix).filter(|n| !n.is_empty())?;
+        let owner = parts.next().filter(|o| !o.is_empty())?;
+        return Some(build(Some(host.to_string()), Some(owner.to_string()), name));
+    }
+
     // Handle scheme-based URLs: http(s)://, ssh://, git://
     if let Some(idx) = s.find("://") {
         let mut rest = &s[idx + 3..];
@@ -40,49 +68,54 @@ pub fn parse_git_url(input: &str) -> Option<ParsedGitUrl> {
             }
         }
         // Split host and path
-        let mut parts = rest.split('/');
-        let host = parts.next().map(|h| h.to_string());
-        let owner = parts.next().map(|o| o.to_string());
-        let name = parts.next().map(|n| strip_git_suffix(n))?;
-        return Some(build(host, owner, name));
+        let (host, path) = rest.split_once('/')?;
+        let (owner, name) = split_owner_name(path)?;
+        return Some(build(Some(host.to_string()), Some(owner), name));
     }
 
     // Handle scp-like syntax: user@host:owner/repo(.git)
     if let (Some(at_idx), Some(colon_idx)) = (s.rfind('@'), s.rfind(':')) {
         if at_idx < colon_idx {
             let host = s.get(at_idx + 1..colon_idx).map(|h| h.to_string());
-            let path = &s[colon_idx + 1..];
-            let mut parts = path.split('/');
-            let owner = parts.next().map(|o| o.to_string());
-            let name = parts.next().map(|n| strip_git_suffix(n))?;
-            return Some(build(host, owner, name));
+            let (owner, name) = split_owner_name(&s[colon_idx + 1..])?;
+            return Some(build(host, Some(owner), name));
         }
     }
 
     // Fallback: try to parse https-like without scheme (host/owner/name)
-    let mut parts = s.split('/');
-    let host = parts
-        .next()
-        .and_then(|h| if h.contains(':') || h.contains('@') { None } else { Some(h.to_string()) });
-    let owner = parts.next().map(|o| o.to_string());
-    if let Some(n) = parts.next() {
-        return Some(build(host, owner, strip_git_suffix(n)));
-    }
+    let (host, path) = s.split_once('/')?;
+    let host = if host.contains(':') || host.contains('@') { None } else { Some(host.to_string()) };
+    let (owner, name) = split_owner_name(path)?;
+    Some(build(host, Some(owner), name))
+}
 
-    None
+/// Lists the remotes of the repository at `repo` as `(name, url)` pairs, in
+/// the order they appear in its git config, with `insteadOf` rules applied.
+/// The config files are read directly, so git is not needed.
+pub fn get_remotes(repo: &str) -> Vec<(String, String)> {
+    remotes_from_config(&read_repo_config(repo))
 }
 
-pub fn get_remote_url(repo: &str) -> String {
-    let output = Command::new("git")
-        .arg("remote")
-        .arg("get-url")
-        .arg("origin")
-        .current_dir(repo)
-        .output()
-        .expect("Failed to get remote url");
-
-    let url = String::from_utf8(output.stdout).unwrap();
-    url.trim().to_string()
+/// Remote names may themselves contain dots.
+fn remotes_from_config(config: &GitConfig) -> Vec<(String, String)> {
+    let mut remotes: Vec<(String, String)> = Vec::new();
+    for (key, url) in config.entries() {
+        let Some(name) = key.strip_prefix("remote.").and_then(|k| k.strip_suffix(".url")) else {
+            continue;
+        };
+        // A remote may list several URLs; the first one is used for fetching.
+        if remotes.iter().any(|(existing, _)| existing == name) {
+            continue;
+        }
+        // A rewrite to a local mirror hides where the repository comes from.
+        let rewritten = config.rewrite_url(url);
+        let url = match parse_git_url(&rewritten) {
+            Some(parsed) if remote_url_is_valid(&parsed) => rewritten,
+            _ => url.to_string(),
+        };
+        remotes.push((name.to_string(), url));
+    }
+    remotes
 }
 
 pub fn remote_url_is_valid(parsed: &ParsedGitUrl) -> bool {
@@ -94,6 +127,45 @@ pub fn remote_url_is_valid(parsed: &ParsedGitUrl) -> bool {
 mod tests {
     use super::*;
 
+    #[test]
+    fn remotes_from_config_lists_every_remote() {
+        let config = GitConfig::parse(
+            "[remote \"origin\"]\n\
+             \turl = git@github.com:me/prog.git\n\
+             [remote \"upstream\"]\n\
+             \turl = gh:bytemain/prog.git\n\
+             [remote \"origin\"]\n\
+             \turl = https://mirror.example.com/me/prog.git\n\
+             [remote \"my.fork\"]\n\
+             \turl = https://github.com/fork/prog\n\
+             [remote \"mirrored\"]\n\
+             \turl = https://example.com/acme/prog\n\
+             [url \"https://github.com/\"]\n\
+             \tinsteadOf = gh:\n\
+             [url \"/srv/mirror/\"]\n\
+             \tinsteadOf = https://example.com/\n",
+        );
+
+        assert_eq!(
+            remotes_from_config(&config),
+            vec![
+                ("origin".to_string(), "git@github.com:me/prog.git".to_string()),
+                ("upstream".to_string(), "https://github.com/bytemain/prog.git".to_string()),
+                ("my.fork".to_string(), "https://github.com/fork/prog".to_string()),
+                ("mirrored".to_string(), "https://example.com/acme/prog".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn parse_forge_page_urls() {
+        let p = parse_git_url("https://github.com/acme/api/tree/main/src").unwrap();
+        assert_eq!((p.host.as_deref(), p.fullname.as_str()), (Some("github.com"), "acme/api"));
+
+        let p = parse_git_url("https://gitlab.com/group/sub/api/-/blob/main/README.md").unwrap();
+        assert_eq!((p.owner.as_deref(), p.name.as_str()), (Some("group/sub"), "api"));
+    }
+
     #[test]
     fn parse_https_with_git_suffix() {
         let p = parse_git_url("https://github.com/owner/repo.git").unwrap();
@@ -168,6 +240,46 @@ mod tests {
         assert!(remote_url_is_valid(&p));
     }
 
+    #[test]
+    fn parse_owner_with_subgroups() {
+        let p = parse_git_url("https://gitlab.com/group/sub/api.git").unwrap();
+        assert_eq!(p.host.as_deref(), Some("gitlab.com"));
+        assert_eq!(p.owner.as_deref(), Some("group/sub"));
+        assert_eq!(p.name, "api");
+        assert_eq!(p.fullname, "group/sub/api");
+
+        let p = parse_git_url("git@gitlab.com:group/sub/api.git").unwrap();
+        assert_eq!(p.owner.as_deref(), Some("group/sub"));
+        assert_eq!(p.name, "api");
+
+        let p = parse_git_url("https://github.com/owner/repo/").unwrap();
+        assert_eq!(p.owner.as_deref(), Some("owner"));
+        assert_eq!(p.name, "repo");
+    }
+
+    #[test]
+    fn parse_file_url() {
+        let p = parse_git_url("file:///srv/git/owner/repo.git").unwrap();
+        assert_eq!(p.host.as_deref(), Some("localhost"));
+        assert_eq!(p.owner.as_deref(), Some("owner"));
+        assert_eq!(p.name, "repo");
+        assert_eq!(p.fullname, "owner/repo");
+        assert!(remote_url_is_valid(&p));
+    }
+
+    #[test]
+    fn parse_file_url_with_host_and_trailing_slash() {
+        let p = parse_git_url("file://nas/git/owner/repo/").unwrap();
+        assert_eq!(p.host.as_deref(), Some("nas"));
+        assert_eq!(p.owner.as_deref(), Some("owner"));
+        assert_eq!(p.name, "repo");
+    }
+
+    #[test]
+    fn invalid_file_url_without_owner() {
+        assert!(parse_git_url("file:///repo").is_none());
+    }
+
     #[test]
     fn invalid_empty_input() {
         assert!(parse_git_url("   ").is_none());
@@ -233,4 +345,57 @@ mod tests {
         assert_eq!(p.owner.as_deref(), Some("owner"));
         assert!(p.name.is_empty());
     }
+
+    /// Compares reading remotes from the config files with asking git, as sync
+    /// used to. Run with
+    /// `cargo test --release bench_get_remotes -- --ignored --nocapture`.
+    #[test]
+    #[ignore]
+    fn bench_get_remotes() {
+        use std::process::Command;
+        use std::time::Instant;
+
+        const REPOS: usize = 200;
+        let temp_dir = tempfile::tempdir().unwrap();
+        let repos: Vec<String> = (0..REPOS)
+            .map(|i| {
+                let repo = temp_dir.path().join(format!("repo-{}", i));
+                std::fs::create_dir(&repo).unwrap();
+                let git = |args: &[&str]| {
+                    Command::new("git").args(args).current_dir(&repo).output().unwrap();
+                };
+                git(&["init"]);
+                git(&["remote", "add", "origin", &format!("https://github.com/acme/repo-{}", i)]);
+                repo.to_string_lossy().to_string()
+            })
+            .collect();
+
+        let start = Instant::now();
+        let native: Vec<_> = repos.iter().map(|repo| get_remotes(repo)).collect();
+        let native_time = start.elapsed();
+
+        let start = Instant::now();
+        let spawned: Vec<_> = repos
+            .iter()
+            .map(|repo| {
+                Command::new("git")
+                    .args(["config", "--get-regexp", r"^remote\..*\.url$"])
+                    .current_dir(repo)
+                    .output()
+                    .unwrap()
+            })
+            .collect();
+        let git_time = start.elapsed();
+
+        println!(
+            "{} repositories: config files {:?}, git config {:?} ({:.0}x)",
+            REPOS,
+            native_time,
+            git_time,
+            git_time.as_secs_f64() / native_time.as_secs_f64()
+        );
+        assert!(native.iter().all(|remotes| remotes.len() == 1));
+        assert!(spawned.iter().all(|output| output.status.success()));
+        assert!(native_time < git_time);
+    }
 }
@@ -0,0 +1,120 @@
+use super::branch::resolve_gitdir;
+use anyhow::bail;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+/// Main working tree of the linked worktree at `repo_path`, found through the
+/// `commondir` file of its gitdir. `None` for a main working tree, for
+/// submodules and for worktrees of a bare repository.
+pub fn main_worktree(repo_path: &str) -> Option<String> {
+    if Path::new(repo_path).join(".git").is_dir() {
+        return None;
+    }
+    let gitdir = resolve_gitdir(repo_path)?;
+    let commondir = fs::read_to_string(gitdir.join("commondir")).ok()?;
+    let common = gitdir.join(commondir.trim()).canonicalize().ok()?;
+    if common.file_name()? != ".git" {
+        return None;
+    }
+    Some(common.parent()?.to_string_lossy().to_string())
+}
+
+/// Whether the repository at `repo_path` has linked worktrees registered.
+pub fn has_worktrees(repo_path: &str) -> bool {
+    resolve_gitdir(repo_path).is_some_and(|gitdir| gitdir.join("worktrees").is_dir())
+}
+
+/// Checks out `branch` of the repository at `repo` into a new worktree at
+/// `path`, creating the branch from `HEAD` with `create`.
+pub fn add_worktree(repo: &str, path: &Path, branch: &str, create: bool) -> anyhow::Result<()> {
+    let path = path.to_string_lossy();
+    let args: Vec<&str> = if create {
+        vec!["worktree", "add", "-b", branch, &path]
+    } else {
+        vec!["worktree", "add", &path, branch]
+    };
+    let status = Command::new("git").args(&args).current_dir(repo).stdin(Stdio::null()).status()?;
+    if !status.success() {
+        bail!("git worktree add failed with {}", status);
+    }
+    Ok(())
+}
+
+/// Drops the administrative files of worktrees of `repo` whose directory is
+/// gone, and returns git's report of what was (or with `dry_run`, would be)
+/// removed.
+pub fn prune_worktrees(repo: &str, dry_run: bool) -> anyhow::Result<Vec<String>> {
+    let mut args = vec!["worktree", "prune", "--verbose"];
+    if dry_run {
+        args.push("--dry-run");
+    }
+    let output = Command::new("git").args(&args).current_dir(repo).stdin(Stdio::null()).output()?;
+    let report: Vec<String> =
+        String::from_utf8_lossy(&output.stderr).lines().map(str::to_string).collect();
+    if !output.status.success() {
+        bail!("git worktree prune failed: {}", report.join(" "));
+    }
+    Ok(report)
+}
+
+/// Points the linked worktrees of `repo` back at it after its working tree
+/// was moved.
+pub fn repair_worktrees(repo: &str) -> anyhow::Result<()> {
+    let output = Command::new("git")
+        .args(["worktree", "repair"])
+        .current_dir(repo)
+        .stdin(Stdio::null())
+        .output()?;
+    if !output.status.success() {
+        bail!("git worktree repair failed: {}", String::from_utf8_lossy(&output.stderr).trim());
+    }
+    Ok(())
+}
+
+/// Where `prog wt add` puts the worktree of `branch`: a sibling of the main
+/// working tree named `<repo>@<branch>`, with `/` in the branch turned into `-`.
+pub fn worktree_path(main: &str, branch: &str) -> Option<PathBuf> {
+    let main = Path::new(main);
+    let name = main.file_name()?.to_string_lossy();
+    Some(main.with_file_name(format!("{}@{}", name, branch.replace('/', "-"))))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_worktree_path() {
+        assert_eq!(
+            worktree_path("/work/github.com/acme/api", "feature/x"),
+            Some(PathBuf::from("/work/github.com/acme/api@feature-x"))
+        );
+    }
+
+    #[test]
+    fn test_main_worktree() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let main = temp_dir.path().join("api");
+        fs::create_dir(&main).unwrap();
+        let main = main.canonicalize().unwrap();
+        let main_str = main.to_str().unwrap();
+        let git = |args: &[&str]| {
+            Command::new("git").args(args).current_dir(&main).output().unwrap();
+        };
+        git(&["init", "--initial-branch=main"]);
+        git(&["-c", "user.email=a@b.c", "-c", "user.name=n", "commit", "--allow-empty", "-m", "i"]);
+
+        let path = worktree_path(main_str, "feature/x").unwrap();
+        add_worktree(main_str, &path, "feature/x", true).unwrap();
+
+        assert!(has_worktrees(main_str));
+        assert_eq!(main_worktree(main_str), None);
+        assert_eq!(main_worktree(path.to_str().unwrap()).as_deref(), Some(main_str));
+
+        fs::remove_dir_all(&path).unwrap();
+        assert_eq!(prune_worktrees(main_str, true).unwrap().len(), 1);
+        assert_eq!(prune_worktrees(main_str, false).unwrap().len(), 1);
+        assert!(prune_worktrees(main_str, true).unwrap().is_empty());
+    }
+}
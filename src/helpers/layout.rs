@@ -0,0 +1,103 @@
+use std::path::PathBuf;
+
+/// Where a repository is placed under its base dir unless `layout` is configured.
+pub const DEFAULT_LAYOUT: &str = "{host}/{owner}/{repo}";
+
+const PLACEHOLDERS: [&str; 3] = ["{host}", "{owner}", "{repo}"];
+
+/// Checks that `layout` is a relative path template that names the repository
+/// and only uses known placeholders.
+pub fn validate(layout: &str) -> Result<(), String> {
+    if !layout.contains("{repo}") {
+        return Err(format!("Layout must contain {{repo}}: {}", layout));
+    }
+    if layout.starts_with('/') || layout.split('/').any(|segment| segment == "..") {
+        return Err(format!("Layout must be a path inside the base dir: {}", layout));
+    }
+
+    let mut rest = layout;
+    while let Some(start) = rest.find('{') {
+        let placeholder = match rest[start..].find('}') {
+            Some(end) => &rest[start..=start + end],
+            None => &rest[start..],
+        };
+        if !PLACEHOLDERS.contains(&placeholder) {
+            return Err(format!("Unknown placeholder {} in layout: {}", placeholder, layout));
+        }
+        rest = &rest[start + placeholder.len()..];
+    }
+    Ok(())
+}
+
+/// Path of a repository relative to its base dir. An owner with subgroups
+/// spans several directories.
+pub fn render(layout: &str, host: &str, owner: &str, repo: &str) -> PathBuf {
+    let rendered = layout.replace("{host}", host).replace("{owner}", owner).replace("{repo}", repo);
+    rendered.split('/').filter(|segment| !segment.is_empty()).collect()
+}
+
+/// Path of the directory holding everything of one host or owner, relative to
+/// the base dir: the layout up to the segment that is exactly `placeholder`.
+/// `None` when the layout has no such directory, e.g. `{owner}-{repo}`.
+pub fn prefix(layout: &str, placeholder: &str, host: &str, owner: &str) -> Option<PathBuf> {
+    let segments: Vec<&str> = layout.split('/').filter(|segment| !segment.is_empty()).collect();
+    let end = segments.iter().position(|segment| *segment == placeholder)?;
+    let prefix = &segments[..=end];
+    if prefix.iter().any(|segment| segment.contains("{repo}")) {
+        return None;
+    }
+    Some(render(&prefix.join("/"), host, owner, ""))
+}
+
+/// How deep below a base dir repositories laid out with `layout` are searched.
+/// Owners with subgroups (`group/sub`) lie deeper and need `discovery_depth`.
+pub fn depth(layout: &str) -> usize {
+    layout.split('/').filter(|segment| !segment.is_empty()).count()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validate() {
+        assert!(validate(DEFAULT_LAYOUT).is_ok());
+        assert!(validate("{owner}-{repo}").is_ok());
+        assert!(validate("{repo}").is_ok());
+        assert!(validate("{host}/{owner}").is_err());
+        assert!(validate("{host}/{name}/{repo}").is_err());
+        assert!(validate("{repo").is_err());
+        assert!(validate("/{repo}").is_err());
+        assert!(validate("../{repo}").is_err());
+    }
+
+    #[test]
+    fn test_render() {
+        assert_eq!(
+            render(DEFAULT_LAYOUT, "gitlab.com", "group/sub", "api"),
+            PathBuf::from("gitlab.com").join("group").join("sub").join("api")
+        );
+        assert_eq!(
+            render("{owner}-{repo}", "github.com", "acme", "api"),
+            PathBuf::from("acme-api")
+        );
+        assert_eq!(render("{repo}", "github.com", "acme", "api"), PathBuf::from("api"));
+    }
+
+    #[test]
+    fn test_prefix() {
+        let owner = prefix(DEFAULT_LAYOUT, "{owner}", "github.com", "acme");
+        assert_eq!(owner, Some(PathBuf::from("github.com").join("acme")));
+        let host = prefix(DEFAULT_LAYOUT, "{host}", "github.com", "acme");
+        assert_eq!(host, Some(PathBuf::from("github.com")));
+        assert_eq!(prefix("{owner}/{repo}", "{host}", "github.com", "acme"), None);
+        assert_eq!(prefix("{owner}-{repo}", "{owner}", "github.com", "acme"), None);
+    }
+
+    #[test]
+    fn test_depth() {
+        assert_eq!(depth(DEFAULT_LAYOUT), 3);
+        assert_eq!(depth("{host}/{repo}"), 2);
+        assert_eq!(depth("{repo}"), 1);
+    }
+}
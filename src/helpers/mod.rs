@@ -1,5 +1,8 @@
 pub mod colors;
+pub mod exclude;
+pub mod fuzzy;
 pub mod git;
+pub mod layout;
 pub mod path;
 pub mod platform;
 pub mod rand;
@@ -48,7 +48,7 @@ pub fn exists(path: &str) -> bool {
 /// # Returns
 ///
 /// * `bool` - true if the directory is effectively empty, false otherwise
-fn is_dir_effectively_empty(path: &Path) -> bool {
+pub fn is_dir_effectively_empty(path: &Path) -> bool {
     match fs::read_dir(path) {
         Ok(entries) => {
             for entry in entries.flatten() {
@@ -3,23 +3,25 @@ use std::io::Write;
 use std::process::{Command, Stdio};
 
 pub fn copy_path(path: &str) {
-    let content = format!("cd {}", path);
-    let success = if cfg!(target_os = "windows") {
-        copy_to_clipboard_windows(&content)
+    if copy_text(&format!("cd {}", path)) {
+        println!("{}", "📋 Copied to clipboard, you can paste it now.".green());
+    } else {
+        eprintln!("Failed to copy to clipboard.");
+    }
+}
+
+/// Copies `content` to the system clipboard. Returns whether it worked.
+pub fn copy_text(content: &str) -> bool {
+    if cfg!(target_os = "windows") {
+        copy_to_clipboard_windows(content)
     } else if cfg!(target_os = "macos") {
-        copy_to_clipboard_macos(&content)
+        copy_to_clipboard_macos(content)
     } else if cfg!(target_os = "linux") {
-        copy_to_clipboard_linux(&content)
+        copy_to_clipboard_linux(content)
     } else {
         // For other systems, we show an error message
         eprintln!("Clipboard functionality is not supported on this platform.");
         false
-    };
-
-    if success {
-        println!("{}", "📋 Copied to clipboard, you can paste it now.".green());
-    } else {
-        eprintln!("Failed to copy to clipboard.");
     }
 }
 
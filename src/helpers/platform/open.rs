@@ -0,0 +1,22 @@
+use std::process::{Command, Stdio};
+
+/// Opens `path` with the platform's default handler (Finder, Explorer, or
+/// whatever `xdg-open` picks) without waiting for it. Returns whether the
+/// opener could be started.
+pub fn open_path(path: &str) -> bool {
+    let mut cmd = if cfg!(target_os = "windows") {
+        let mut cmd = Command::new("explorer");
+        cmd.arg(path);
+        cmd
+    } else if cfg!(target_os = "macos") {
+        let mut cmd = Command::new("open");
+        cmd.arg(path);
+        cmd
+    } else {
+        let mut cmd = Command::new("xdg-open");
+        cmd.arg(path);
+        cmd
+    };
+
+    cmd.stdin(Stdio::null()).stdout(Stdio::null()).stderr(Stdio::null()).spawn().is_ok()
+}
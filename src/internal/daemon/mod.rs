@@ -0,0 +1,112 @@
+//! `prog daemon` keeps the index up to date by watching the base dirs, and
+//! answers the CLI over a Unix socket in `~/.prog`. One JSON request and one
+//! JSON response per connection, each on a single line.
+
+#[cfg(target_os = "linux")]
+mod watcher;
+
+use crate::constants;
+use crate::context::Context;
+use crate::helpers::path::get_config_path;
+use crate::internal::sync::SyncSummary;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+#[cfg(unix)]
+use std::time::Duration;
+
+/// How long the CLI waits for the daemon before syncing by itself.
+#[cfg(unix)]
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(tag = "request", rename_all = "snake_case")]
+pub enum Request {
+    Status,
+    /// Apply the changes seen on disk that are still waiting to settle.
+    Flush,
+    /// Scan every base dir again.
+    Sync,
+    Stop,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(tag = "response", rename_all = "snake_case")]
+pub enum Response {
+    Status {
+        pid: u32,
+        /// Number of directories watched.
+        watches: usize,
+        /// Number of directories waiting to be scanned.
+        pending: usize,
+    },
+    Synced {
+        summary: SyncSummary,
+    },
+    Stopping,
+    Error {
+        message: String,
+    },
+}
+
+pub fn socket_path() -> PathBuf {
+    get_config_path(constants::DAEMON_SOCKET_FILE)
+}
+
+/// Sends `request` to the running daemon. `None` when no daemon answers.
+#[cfg(unix)]
+pub fn request(request: &Request) -> Option<Response> {
+    use std::io::{BufRead, BufReader, Write};
+    use std::os::unix::net::UnixStream;
+
+    let mut stream = UnixStream::connect(socket_path()).ok()?;
+    stream.set_read_timeout(Some(REQUEST_TIMEOUT)).ok()?;
+    stream.set_write_timeout(Some(REQUEST_TIMEOUT)).ok()?;
+
+    let mut line = serde_json::to_string(request).ok()?;
+    line.push('\n');
+    stream.write_all(line.as_bytes()).ok()?;
+
+    let mut reply = String::new();
+    BufReader::new(stream).read_line(&mut reply).ok()?;
+    match serde_json::from_str(&reply) {
+        Ok(response) => Some(response),
+        Err(e) => {
+            log::warn!("Unexpected reply from the daemon: {}", e);
+            None
+        }
+    }
+}
+
+#[cfg(not(unix))]
+pub fn request(_request: &Request) -> Option<Response> {
+    None
+}
+
+/// Runs the daemon in the foreground until it is asked to stop.
+#[cfg(target_os = "linux")]
+pub fn serve(c: &Context) -> anyhow::Result<()> {
+    watcher::serve(c)
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn serve(_c: &Context) -> anyhow::Result<()> {
+    anyhow::bail!("The daemon watches directories with inotify, which is only available on Linux")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_protocol_is_one_json_line() {
+        assert_eq!(serde_json::to_string(&Request::Flush).unwrap(), r#"{"request":"flush"}"#);
+
+        let response = Response::Status { pid: 42, watches: 7, pending: 0 };
+        let line = serde_json::to_string(&response).unwrap();
+        assert_eq!(line, r#"{"response":"status","pid":42,"watches":7,"pending":0}"#);
+        assert!(matches!(
+            serde_json::from_str::<Response>(&line).unwrap(),
+            Response::Status { pid: 42, watches: 7, pending: 0 }
+        ));
+    }
+}
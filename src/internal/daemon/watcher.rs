@@ -0,0 +1,384 @@
+use super::{Request, Response, socket_path};
+use crate::context::Context;
+use crate::helpers::exclude::IGNORE_FILE;
+use crate::internal::sync::{SyncSummary, sync, sync_paths};
+use anyhow::bail;
+use ignore::WalkBuilder;
+use inotify::{EventMask, Inotify, WatchDescriptor, WatchMask};
+use log::{debug, error, info, warn};
+use std::collections::{BTreeMap, HashMap};
+use std::ffi::OsString;
+use std::io::{self, BufRead, BufReader, ErrorKind, Write};
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{Receiver, RecvTimeoutError, Sender, channel};
+use std::time::{Duration, Instant};
+use std::{fs, process, thread};
+
+/// How long the base dirs must stay quiet before the changes are applied, so
+/// a clone or a `mv` of a whole tree is scanned once.
+const SETTLE_TIME: Duration = Duration::from_millis(500);
+/// How often pending events are looked at while no request comes in.
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+const WATCH_MASK: WatchMask = WatchMask::CREATE
+    .union(WatchMask::DELETE)
+    .union(WatchMask::MOVED_FROM)
+    .union(WatchMask::MOVED_TO)
+    .union(WatchMask::ONLYDIR);
+
+type Job = (Request, Sender<Response>);
+
+#[derive(Debug, Clone)]
+struct Watched {
+    path: PathBuf,
+    base_dir: String,
+    /// Levels below the base dir.
+    depth: usize,
+}
+
+/// Watches every directory in the base dirs where a repository may appear,
+/// down to the discovery depth and never inside a repository.
+pub(super) struct Watcher {
+    inotify: Inotify,
+    watches: HashMap<WatchDescriptor, Watched>,
+    base_dirs: Vec<String>,
+    max_depth: usize,
+    /// Directories to scan once the events settle, with their base dir.
+    pending: BTreeMap<PathBuf, String>,
+    /// Events were lost, so only a full sync can tell what changed.
+    overflowed: bool,
+    last_event: Instant,
+    buffer: Vec<u8>,
+}
+
+impl Watcher {
+    pub(super) fn new(base_dirs: Vec<String>, max_depth: usize) -> io::Result<Self> {
+        let mut watcher = Self {
+            inotify: Inotify::init()?,
+            watches: HashMap::new(),
+            base_dirs,
+            max_depth,
+            pending: BTreeMap::new(),
+            overflowed: false,
+            last_event: Instant::now(),
+            buffer: vec![0; 64 * 1024],
+        };
+        watcher.watch_base_dirs();
+        Ok(watcher)
+    }
+
+    fn watch_base_dirs(&mut self) {
+        for (wd, _) in self.watches.drain() {
+            let _ = self.inotify.watches().remove(wd);
+        }
+        for base_dir in self.base_dirs.clone() {
+            self.watch_tree(Path::new(&base_dir), &base_dir, 0);
+        }
+    }
+
+    /// Watches `root`, `depth` levels below `base_dir`, and the directories
+    /// below it that are not inside a repository.
+    fn watch_tree(&mut self, root: &Path, base_dir: &str, depth: usize) {
+        if depth > self.max_depth || !root.is_dir() {
+            return;
+        }
+
+        let walker = WalkBuilder::new(root)
+            .max_depth(Some(self.max_depth - depth))
+            .hidden(true)
+            .add_custom_ignore_filename(IGNORE_FILE)
+            .filter_entry(|entry| {
+                entry.file_type().is_some_and(|file_type| file_type.is_dir())
+                    && (entry.depth() == 0 || !entry.path().parent().is_some_and(is_repo))
+            })
+            .build();
+        for entry in walker.flatten() {
+            let path = entry.path().to_path_buf();
+            match self.inotify.watches().add(&path, WATCH_MASK) {
+                Ok(wd) => {
+                    let watched = Watched {
+                        path,
+                        base_dir: base_dir.to_string(),
+                        depth: depth + entry.depth(),
+                    };
+                    self.watches.insert(wd, watched);
+                }
+                Err(e) => warn!("Unable to watch {}: {}", path.display(), e),
+            }
+        }
+    }
+
+    /// Stops watching `root` and everything below it, after it was moved away.
+    fn unwatch_tree(&mut self, root: &Path) {
+        let gone: Vec<WatchDescriptor> = self
+            .watches
+            .iter()
+            .filter(|(_, watched)| watched.path.starts_with(root))
+            .map(|(wd, _)| wd.clone())
+            .collect();
+        for wd in gone {
+            self.watches.remove(&wd);
+            let _ = self.inotify.watches().remove(wd);
+        }
+    }
+
+    /// Reads the events that arrived since the last call, without blocking.
+    pub(super) fn read_events(&mut self) {
+        loop {
+            let events: Vec<(WatchDescriptor, EventMask, Option<OsString>)> =
+                match self.inotify.read_events(&mut self.buffer) {
+                    Ok(events) => events
+                        .map(|event| (event.wd, event.mask, event.name.map(OsString::from)))
+                        .collect(),
+                    Err(e) if e.kind() == ErrorKind::WouldBlock => return,
+                    Err(e) => {
+                        error!("Failed to read file system events: {}", e);
+                        return;
+                    }
+                };
+            if events.is_empty() {
+                return;
+            }
+            for (wd, mask, name) in events {
+                self.handle_event(wd, mask, name);
+            }
+        }
+    }
+
+    fn handle_event(&mut self, wd: WatchDescriptor, mask: EventMask, name: Option<OsString>) {
+        if mask.contains(EventMask::Q_OVERFLOW) {
+            warn!("File system events were lost, scanning every base dir again");
+            self.overflowed = true;
+            self.last_event = Instant::now();
+            return;
+        }
+        if mask.contains(EventMask::IGNORED) {
+            self.watches.remove(&wd);
+            return;
+        }
+        let (Some(watched), Some(name)) = (self.watches.get(&wd).cloned(), name) else {
+            return;
+        };
+
+        if name == ".git" {
+            // A repository was created, or stopped being one.
+            debug!("{:?} of .git in {}", mask, watched.path.display());
+            self.pending.insert(watched.path, watched.base_dir);
+            self.last_event = Instant::now();
+            return;
+        }
+
+        let is_hidden = name.to_string_lossy().starts_with('.');
+        if !mask.contains(EventMask::ISDIR)
+            || is_hidden
+            || watched.depth >= self.max_depth
+            || is_repo(&watched.path)
+        {
+            return;
+        }
+
+        let path = watched.path.join(&name);
+        debug!("{:?} of {}", mask, path.display());
+        if mask.intersects(EventMask::CREATE | EventMask::MOVED_TO) {
+            self.watch_tree(&path, &watched.base_dir, watched.depth + 1);
+        } else if mask.contains(EventMask::MOVED_FROM) {
+            self.unwatch_tree(&path);
+        }
+        self.pending.insert(path, watched.base_dir);
+        self.last_event = Instant::now();
+    }
+
+    fn has_changes(&self) -> bool {
+        self.overflowed || !self.pending.is_empty()
+    }
+
+    pub(super) fn is_settled(&self) -> bool {
+        self.has_changes() && self.last_event.elapsed() >= SETTLE_TIME
+    }
+
+    /// Brings the index in line with the directories changed since the last
+    /// call. A directory moved between two of them is recorded as moved.
+    pub(super) fn apply(&mut self, c: &Context) -> SyncSummary {
+        if self.overflowed {
+            return self.rescan(c);
+        }
+
+        let mut roots: Vec<(String, PathBuf)> = Vec::new();
+        for (path, base_dir) in std::mem::take(&mut self.pending) {
+            // Sorted, so a directory comes right before the ones inside it.
+            if !roots.last().is_some_and(|(_, root)| path.starts_with(root)) {
+                roots.push((base_dir, path));
+            }
+        }
+        sync_paths(c, &roots)
+    }
+
+    /// Watches the base dirs from scratch and scans all of them.
+    pub(super) fn rescan(&mut self, c: &Context) -> SyncSummary {
+        self.pending.clear();
+        self.overflowed = false;
+        self.watch_base_dirs();
+        sync(c, true)
+    }
+}
+
+fn is_repo(path: &Path) -> bool {
+    path.join(".git").exists()
+}
+
+pub(super) fn serve(c: &Context) -> anyhow::Result<()> {
+    let socket_path = socket_path();
+    if super::request(&Request::Status).is_some() {
+        bail!("The daemon is already running");
+    }
+    // Nothing answered, so a socket left behind is stale.
+    let _ = fs::remove_file(&socket_path);
+    let listener = UnixListener::bind(&socket_path)?;
+
+    let mut watcher = Watcher::new(c.config().base_dirs(), c.config().discovery_depth())?;
+    let summary = sync(c, true);
+    info!(
+        "Watching {} directories, {} repositories in the index",
+        watcher.watches.len(),
+        summary.total
+    );
+
+    let (jobs, incoming) = channel::<Job>();
+    thread::spawn(move || accept(listener, jobs));
+    let result = run_loop(c, &mut watcher, incoming);
+
+    let _ = fs::remove_file(&socket_path);
+    result
+}
+
+fn run_loop(c: &Context, watcher: &mut Watcher, incoming: Receiver<Job>) -> anyhow::Result<()> {
+    loop {
+        watcher.read_events();
+        if watcher.is_settled() {
+            log_summary(&watcher.apply(c));
+        }
+
+        let (request, reply) = match incoming.recv_timeout(POLL_INTERVAL) {
+            Ok(job) => job,
+            Err(RecvTimeoutError::Timeout) => continue,
+            Err(RecvTimeoutError::Disconnected) => bail!("The daemon socket was closed"),
+        };
+        debug!("Request: {:?}", request);
+        let response = match request {
+            Request::Status => Response::Status {
+                pid: process::id(),
+                watches: watcher.watches.len(),
+                pending: watcher.pending.len(),
+            },
+            Request::Flush => {
+                watcher.read_events();
+                let summary =
+                    if watcher.has_changes() { watcher.apply(c) } else { SyncSummary::default() };
+                log_summary(&summary);
+                Response::Synced { summary }
+            }
+            Request::Sync => {
+                let summary = watcher.rescan(c);
+                log_summary(&summary);
+                Response::Synced { summary }
+            }
+            Request::Stop => Response::Stopping,
+        };
+        let stopping = matches!(response, Response::Stopping);
+        let _ = reply.send(response);
+        if stopping {
+            info!("Stopping");
+            return Ok(());
+        }
+    }
+}
+
+/// Hands every request on the socket to the main loop and writes back its
+/// response. Connections are served one at a time.
+fn accept(listener: UnixListener, jobs: Sender<Job>) {
+    for stream in listener.incoming() {
+        match stream {
+            Ok(stream) => {
+                if let Err(e) = answer(stream, &jobs) {
+                    warn!("Failed to answer a request: {}", e);
+                }
+            }
+            Err(e) => warn!("Failed to accept a connection: {}", e),
+        }
+    }
+}
+
+fn answer(stream: UnixStream, jobs: &Sender<Job>) -> anyhow::Result<()> {
+    let mut line = String::new();
+    BufReader::new(&stream).read_line(&mut line)?;
+    let response = match serde_json::from_str::<Request>(&line) {
+        Ok(request) => {
+            let (reply, response) = channel();
+            jobs.send((request, reply))?;
+            response.recv()?
+        }
+        Err(e) => Response::Error { message: format!("Invalid request: {}", e) },
+    };
+
+    let mut line = serde_json::to_string(&response)?;
+    line.push('\n');
+    (&stream).write_all(line.as_bytes())?;
+    Ok(())
+}
+
+fn log_summary(summary: &SyncSummary) {
+    for path in &summary.added {
+        info!("added {}", path);
+    }
+    for path in &summary.removed {
+        info!("removed {}", path);
+    }
+    for moved in &summary.moved {
+        info!("moved {} -> {}", moved.from, moved.to);
+    }
+    for changed in &summary.remote_changed {
+        info!("remote of {} changed from {} to {}", changed.path, changed.from, changed.to);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::process::Command;
+
+    fn settle(watcher: &mut Watcher) -> Vec<PathBuf> {
+        thread::sleep(Duration::from_millis(50));
+        watcher.read_events();
+        std::mem::take(&mut watcher.pending).into_keys().collect()
+    }
+
+    #[test]
+    fn test_watcher_sees_repositories_come_and_go() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let base = temp_dir.path().canonicalize().unwrap();
+        let owner = base.join("github.com").join("acme");
+        fs::create_dir_all(&owner).unwrap();
+
+        let mut watcher = Watcher::new(vec![base.to_string_lossy().to_string()], 3).unwrap();
+        assert_eq!(watcher.watches.len(), 3);
+
+        // A clone creates the directory first and `.git` inside it right after.
+        let repo = owner.join("api");
+        fs::create_dir(&repo).unwrap();
+        let created = settle(&mut watcher);
+        assert_eq!(created, vec![repo.clone()]);
+        Command::new("git").arg("init").current_dir(&repo).output().unwrap();
+        assert_eq!(settle(&mut watcher), vec![repo.clone()]);
+
+        let renamed = owner.join("api-v2");
+        fs::rename(&repo, &renamed).unwrap();
+        assert_eq!(settle(&mut watcher), vec![repo.clone(), renamed.clone()]);
+
+        // Nothing inside a repository is watched but its `.git`.
+        fs::create_dir(renamed.join("src")).unwrap();
+        assert!(settle(&mut watcher).is_empty());
+        fs::remove_dir_all(renamed.join(".git")).unwrap();
+        assert_eq!(settle(&mut watcher), vec![renamed]);
+    }
+}
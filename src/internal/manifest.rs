@@ -0,0 +1,163 @@
+use crate::context::database::models::Repo;
+use crate::helpers::path::contract_tilde;
+use log::warn;
+use serde::{Deserialize, Serialize};
+use std::path::{Component, Path};
+
+const MANIFEST_VERSION: u32 = 1;
+
+fn default_version() -> u32 {
+    MANIFEST_VERSION
+}
+
+/// A portable description of a workspace: every repository a machine should
+/// have, where it lives, and how it was cloned.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Manifest {
+    #[serde(default = "default_version")]
+    pub version: u32,
+    #[serde(default, rename = "repo")]
+    pub repos: Vec<ManifestRepo>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct ManifestRepo {
+    pub remote: String,
+    /// Location of the clone relative to its base dir, always `/` separated,
+    /// e.g. `github.com/bytemain/prog`.
+    pub path: String,
+    /// Base dir the repository belongs to, with the home directory as `~`.
+    pub base: String,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub clone_args: Vec<String>,
+}
+
+impl Manifest {
+    /// Builds a manifest from index records. Records without a remote, or that
+    /// live outside their base dir, cannot be reproduced and are skipped.
+    pub fn from_repos(repos: &[Repo]) -> Self {
+        let mut entries = Vec::new();
+        for repo in repos {
+            if repo.remote_url.is_empty() {
+                warn!("Skipping repository without remote: {}", repo.full_path);
+                continue;
+            }
+            let Some(path) = relative_layout_path(&repo.base_dir, &repo.full_path) else {
+                warn!("Skipping repository outside its base dir: {}", repo.full_path);
+                continue;
+            };
+
+            entries.push(ManifestRepo {
+                remote: repo.remote_url.clone(),
+                path,
+                base: contract_tilde(&repo.base_dir),
+                tags: repo.tags.clone(),
+                clone_args: repo.clone_args.clone(),
+            });
+        }
+
+        Self { version: MANIFEST_VERSION, repos: entries }
+    }
+
+    pub fn parse(contents: &str) -> Result<Self, String> {
+        let manifest: Manifest =
+            toml::from_str(contents).map_err(|e| format!("Invalid workspace manifest: {}", e))?;
+        if manifest.version > MANIFEST_VERSION {
+            return Err(format!(
+                "Workspace manifest version {} is not supported (expected {})",
+                manifest.version, MANIFEST_VERSION
+            ));
+        }
+        for entry in &manifest.repos {
+            if !is_safe_relative_path(&entry.path) {
+                return Err(format!("Invalid path in workspace manifest: {}", entry.path));
+            }
+        }
+        Ok(manifest)
+    }
+
+    pub fn to_toml(&self) -> Result<String, String> {
+        toml::to_string(self).map_err(|e| format!("Unable to serialize workspace manifest: {}", e))
+    }
+}
+
+/// Returns `full_path` relative to `base_dir` using `/` separators.
+fn relative_layout_path(base_dir: &str, full_path: &str) -> Option<String> {
+    let relative = Path::new(full_path).strip_prefix(base_dir).ok()?;
+    let parts: Vec<String> =
+        relative.components().map(|c| c.as_os_str().to_string_lossy().to_string()).collect();
+    if parts.is_empty() { None } else { Some(parts.join("/")) }
+}
+
+/// A manifest path must stay inside its base dir.
+fn is_safe_relative_path(path: &str) -> bool {
+    let path = Path::new(path);
+    !path.as_os_str().is_empty() && path.components().all(|c| matches!(c, Component::Normal(_)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn repo(base_dir: &str, full_path: &str, remote_url: &str) -> Repo {
+        Repo {
+            base_dir: base_dir.to_string(),
+            full_path: full_path.to_string(),
+            remote_url: remote_url.to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_from_repos_uses_relative_paths() {
+        let mut with_tags = repo("/base", "/base/github.com/user/prog", "https://x/user/prog.git");
+        with_tags.tags = vec!["work".to_string()];
+        with_tags.clone_args = vec!["--depth".to_string(), "1".to_string()];
+
+        let manifest = Manifest::from_repos(&[
+            with_tags,
+            repo("/base", "/base/github.com/user/local", ""),
+            repo("/base", "/elsewhere/thing", "https://x/user/thing.git"),
+        ]);
+
+        assert_eq!(manifest.repos.len(), 1);
+        assert_eq!(manifest.repos[0].path, "github.com/user/prog");
+        assert_eq!(manifest.repos[0].base, "/base");
+        assert_eq!(manifest.repos[0].tags, vec!["work"]);
+        assert_eq!(manifest.repos[0].clone_args, vec!["--depth", "1"]);
+    }
+
+    #[test]
+    fn test_manifest_roundtrip() {
+        let manifest = Manifest::from_repos(&[repo(
+            "/base",
+            "/base/github.com/user/prog",
+            "https://github.com/user/prog.git",
+        )]);
+
+        let serialized = manifest.to_toml().unwrap();
+        assert!(serialized.contains("[[repo]]"));
+
+        let parsed = Manifest::parse(&serialized).unwrap();
+        assert_eq!(parsed.repos, manifest.repos);
+    }
+
+    #[test]
+    fn test_parse_rejects_escaping_paths() {
+        let manifest = r#"
+[[repo]]
+remote = "https://github.com/user/prog.git"
+path = "../../etc"
+base = "~/work"
+"#;
+        assert!(Manifest::parse(manifest).is_err());
+    }
+
+    #[test]
+    fn test_parse_rejects_newer_versions() {
+        assert!(Manifest::parse("version = 99").is_err());
+        assert!(Manifest::parse("").unwrap().repos.is_empty());
+    }
+}
@@ -1 +1,3 @@
+pub mod daemon;
+pub mod manifest;
 pub mod sync;
@@ -1,9 +1,15 @@
 use crate::context::Context;
-use crate::helpers::git::get_remote_url;
-use crate::helpers::git::parse_git_url;
+use crate::context::database::Database;
+use crate::context::database::models::{LOCAL_HOST, Repo, RepoRemote, primary_remote};
+use crate::helpers::colors::Colorize;
+use crate::helpers::exclude::{Excludes, IGNORE_FILE};
+use crate::helpers::git::{Submodule, get_remotes, get_submodules, main_worktree};
 use ignore::WalkBuilder;
-use log::{error, warn};
+use log::{debug, error, warn};
 use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::PathBuf;
 use std::{path::Path, sync::mpsc::channel, time::Instant};
 
 #[derive(Debug, Clone)]
@@ -14,27 +20,205 @@ pub struct SyncItem {
     pub remote_url: String,
     pub base_dir: String,
     pub full_path: String,
+    pub remotes: Vec<RepoRemote>,
+    /// Main working tree when this is a linked git worktree.
+    pub worktree_of: Option<String>,
+    pub submodules: Vec<Submodule>,
 }
 
-fn read_repo_from_dir(dir: &str) -> Vec<SyncItem> {
-    let mut repos: Vec<SyncItem> = Vec::new();
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct MovedRepo {
+    pub from: String,
+    pub to: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct RemoteChange {
+    pub path: String,
+    pub from: String,
+    pub to: String,
+}
+
+/// What a sync changed in the index.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SyncSummary {
+    /// Number of repositories in the index after the sync.
+    pub total: usize,
+    pub added: Vec<String>,
+    pub removed: Vec<String>,
+    /// Repositories whose directory changed but whose remote stayed the same.
+    pub moved: Vec<MovedRepo>,
+    pub remote_changed: Vec<RemoteChange>,
+}
+
+impl SyncSummary {
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty()
+            && self.removed.is_empty()
+            && self.moved.is_empty()
+            && self.remote_changed.is_empty()
+    }
+}
+
+/// Brings the index in line with the repositories found on disk.
+///
+/// Only records that changed are touched, so creation times and any other
+/// metadata on a record survive. A record that disappeared from one path while
+/// a repository with the same remote appeared at another is treated as moved.
+pub(crate) fn reconcile(db: &mut Database, items: Vec<SyncItem>) -> SyncSummary {
+    reconcile_scoped(db, items, |_| true)
+}
+
+/// Like [`reconcile`], but only records whose path is `in_scope` are compared
+/// with `items`; the others are left as they are.
+fn reconcile_scoped<F>(db: &mut Database, items: Vec<SyncItem>, in_scope: F) -> SyncSummary
+where
+    F: Fn(&str) -> bool,
+{
+    let mut summary = SyncSummary::default();
+
+    let mut scanned: BTreeMap<String, SyncItem> =
+        items.into_iter().map(|item| (item.full_path.clone(), item)).collect();
+
+    let mut missing: Vec<Repo> = Vec::new();
+    for repo in db.get_all_items().into_iter().filter(|repo| in_scope(&repo.full_path)) {
+        let Some(item) = scanned.remove(&repo.full_path) else {
+            missing.push(repo);
+            continue;
+        };
+
+        if repo.remote_url != item.remote_url {
+            summary.remote_changed.push(RemoteChange {
+                path: repo.full_path.clone(),
+                from: repo.remote_url.clone(),
+                to: item.remote_url.clone(),
+            });
+        }
+        if repo.remote_url != item.remote_url
+            || repo.host != item.host
+            || repo.owner != item.owner
+            || repo.repo != item.repo
+            || repo.base_dir != item.base_dir
+            || repo.remotes != item.remotes
+            || repo.worktree_of != item.worktree_of
+            || repo.submodules != item.submodules
+        {
+            record_sync_item(db, &item);
+        }
+    }
+
+    // Whatever is left in `scanned` is new on disk. Repositories without a
+    // remote cannot be told apart, and worktrees share the remote of their
+    // main repository, so neither is ever treated as moved.
+    for item in scanned.into_values() {
+        let moved_from = missing.iter().position(|repo| {
+            !item.remote_url.is_empty()
+                && item.worktree_of.is_none()
+                && repo.worktree_of.is_none()
+                && repo.remote_url == item.remote_url
+        });
+        match moved_from {
+            Some(index) => {
+                let repo = missing.remove(index);
+                db.move_record(&repo.full_path, &item.full_path, &item.base_dir);
+                summary.moved.push(MovedRepo { from: repo.full_path, to: item.full_path.clone() });
+                if repo.host != item.host
+                    || repo.owner != item.owner
+                    || repo.repo != item.repo
+                    || repo.remotes != item.remotes
+                    || repo.worktree_of != item.worktree_of
+                    || repo.submodules != item.submodules
+                {
+                    record_sync_item(db, &item);
+                }
+            }
+            None => {
+                record_sync_item(db, &item);
+                summary.added.push(item.full_path);
+            }
+        }
+    }
+
+    for repo in missing {
+        db.remove(&repo.full_path);
+        summary.removed.push(repo.full_path);
+    }
+
+    summary.total = db.size();
+    summary
+}
+
+fn record_sync_item(db: &mut Database, item: &SyncItem) {
+    db.record_item(
+        &item.base_dir,
+        &item.remote_url,
+        &item.host,
+        &item.repo,
+        &item.owner,
+        &item.full_path,
+    );
+    db.update_record(&item.full_path, |record| {
+        record.remotes = item.remotes.clone();
+        record.worktree_of = item.worktree_of.clone();
+        record.submodules = item.submodules.clone();
+    });
+}
+
+/// A repository without remotes, indexed under [`LOCAL_HOST`] by its directory name.
+fn local_item(base_dir: &str, path: &Path) -> SyncItem {
+    SyncItem {
+        host: LOCAL_HOST.to_string(),
+        repo: path.file_name().map(|name| name.to_string_lossy().to_string()).unwrap_or_default(),
+        owner: String::new(),
+        remote_url: String::new(),
+        base_dir: base_dir.to_string(),
+        full_path: path.display().to_string(),
+        remotes: Vec::new(),
+        worktree_of: main_worktree(&path.to_string_lossy()),
+        submodules: get_submodules(&path.to_string_lossy()),
+    }
+}
+
+fn read_repo_from_dir(
+    dir: &str,
+    preferred_remotes: &[String],
+    max_depth: usize,
+    excludes: &Excludes,
+) -> Vec<SyncItem> {
     let dir_path = Path::new(dir);
     if !dir_path.exists() {
         warn!("Skipping missing base directory: {}", dir);
-        return repos;
+        return Vec::new();
     }
     if !dir_path.is_dir() {
         warn!("Skipping non-directory base path: {}", dir);
+        return Vec::new();
+    }
+    walk_repos(dir, dir_path, preferred_remotes, max_depth, excludes)
+}
+
+/// Finds the repositories at most `max_depth` levels below `root`, a directory
+/// in (or equal to) `base_dir`, that are not excluded.
+fn walk_repos(
+    base_dir: &str,
+    root: &Path,
+    preferred_remotes: &[String],
+    max_depth: usize,
+    excludes: &Excludes,
+) -> Vec<SyncItem> {
+    let mut repos: Vec<SyncItem> = Vec::new();
+    if !root.is_dir() {
         return repos;
     }
 
     let (tx, rx) = channel::<SyncItem>();
     let threads = std::thread::available_parallelism().map_or(1, |n| n.get()).min(12);
 
-    WalkBuilder::new(dir)
+    WalkBuilder::new(root)
         .threads(threads)
-        .max_depth(Some(3))
+        .max_depth(Some(max_depth))
         .hidden(true)
+        .add_custom_ignore_filename(IGNORE_FILE)
         .filter_entry(|entry| entry.file_type().map(|file_type| file_type.is_dir()).unwrap_or(false))
         .build_parallel()
         .run(|| {
@@ -43,6 +227,10 @@ fn read_repo_from_dir(dir: &str) -> Vec<SyncItem> {
                 match result_entry {
                     Ok(entry) => {
                         let path = entry.path();
+                        if excludes.excludes_path(path) {
+                            debug!("Excluded: {}", path.display());
+                            return ignore::WalkState::Skip;
+                        }
                         let dot_git_path = path.join(".git");
                         // Check if .git is a directory (standard for git repos) or a file (for worktrees)
                         if !dot_git_path.exists() {
@@ -50,54 +238,55 @@ fn read_repo_from_dir(dir: &str) -> Vec<SyncItem> {
                         }
 
                         let full_path_str = path.display().to_string();
+                        let remotes: Vec<RepoRemote> = get_remotes(&full_path_str)
+                            .iter()
+                            .map(|(name, url)| RepoRemote::new(name, url))
+                            .collect();
+                        if remotes.is_empty() {
+                            if excludes.excludes_remote(LOCAL_HOST, "") {
+                                debug!("Excluded host {}: {}", LOCAL_HOST, full_path_str);
+                                return ignore::WalkState::Skip;
+                            }
+                            debug!("No remote for git repository: {}. Recording it as local.", full_path_str);
+                            if let Err(e) = tx_clone.send(local_item(base_dir, path)) {
+                                error!("Failed to send SyncItem on channel: {}. Quitting walk.", e);
+                                return ignore::WalkState::Quit;
+                            }
+                            return ignore::WalkState::Skip;
+                        }
 
-                        let remote_url_str = get_remote_url(&full_path_str);
-                        if remote_url_str.is_empty() {
-                            log::warn!("Could not determine remote URL for git repository: {}. Skipping item.", full_path_str);
-                            return ignore::WalkState::Continue;
+                        let Some(primary) = primary_remote(&remotes, preferred_remotes).cloned() else {
+                            log::error!(
+                                "No remote of {} has a valid Git URL (host, owner and repo name). Skipping item.",
+                                full_path_str
+                            );
+                            return ignore::WalkState::Skip;
+                        };
+                        if excludes.excludes_remote(&primary.host, &primary.owner) {
+                            debug!("Excluded remote {}: {}", primary.url, full_path_str);
+                            return ignore::WalkState::Skip;
                         }
 
-                        match parse_git_url(&remote_url_str) {
-                            Some(url_parsed) => {
-                                if url_parsed.host.as_deref().map(|s| s.trim().is_empty()).unwrap_or(true)
-                                    || url_parsed.owner.as_deref().map(|s| s.trim().is_empty()).unwrap_or(true)
-                                    || url_parsed.name.trim().is_empty()
-                                {
-                                    log::error!(
-                                        "Invalid Git URL '{}' (missing host, owner, or repo name) for path: {}. Skipping item.",
-                                        remote_url_str,
-                                        full_path_str
-                                    );
-                                    return ignore::WalkState::Continue;
-                                }
-
-                                let host_name = url_parsed.host.unwrap();
-                                let owner_name = url_parsed.owner.unwrap();
-                                let repo_name = url_parsed.name;
-
-                                let item = SyncItem {
-                                    base_dir: dir.to_string(),
-                                    host: host_name,
-                                    repo: repo_name,
-                                    owner: owner_name,
-                                    remote_url: remote_url_str.clone(),
-                                    full_path: full_path_str,
-                                };
-
-                                if let Err(e) = tx_clone.send(item) {
-                                    error!("Failed to send SyncItem on channel: {}. Quitting walk.", e);
-                                    return ignore::WalkState::Quit; // Critical error in channel communication.
-                                }
-                            }
-                            None => {
-                                log::error!(
-                                    "Failed to parse remote URL '{}' for git repository at '{}'. Skipping item.",
-                                    remote_url_str,
-                                    full_path_str
-                                );
-                                return ignore::WalkState::Continue;
-                            }
+                        let item = SyncItem {
+                            base_dir: base_dir.to_string(),
+                            host: primary.host,
+                            repo: primary.repo,
+                            owner: primary.owner,
+                            remote_url: primary.url,
+                            worktree_of: main_worktree(&full_path_str),
+                            submodules: get_submodules(&full_path_str),
+                            full_path: full_path_str,
+                            remotes,
+                        };
+
+                        if let Err(e) = tx_clone.send(item) {
+                            error!("Failed to send SyncItem on channel: {}. Quitting walk.", e);
+                            return ignore::WalkState::Quit; // Critical error in channel communication.
                         }
+                        // Whatever is below a repository belongs to it, so its
+                        // directories are not searched (the walk may go deeper
+                        // than the repositories of other owners).
+                        return ignore::WalkState::Skip;
                     }
                     Err(err) => {
                         // Log errors for individual entries but continue the walk.
@@ -123,7 +312,224 @@ fn read_repo_from_dir(dir: &str) -> Vec<SyncItem> {
 
 #[cfg(test)]
 mod tests {
-    use super::read_repo_from_dir;
+    use super::*;
+
+    fn item(name: &str, full_path: &str) -> SyncItem {
+        SyncItem {
+            host: "github.com".to_string(),
+            repo: name.to_string(),
+            owner: "user".to_string(),
+            remote_url: format!("https://github.com/user/{}.git", name),
+            base_dir: "/base".to_string(),
+            full_path: full_path.to_string(),
+            remotes: vec![RepoRemote::new(
+                "origin",
+                &format!("https://github.com/user/{}.git", name),
+            )],
+            worktree_of: None,
+            submodules: Vec::new(),
+        }
+    }
+
+    fn db_with(items: Vec<SyncItem>) -> Database {
+        let mut db = Database::in_memory();
+        reconcile(&mut db, items);
+        db
+    }
+
+    #[test]
+    fn reconcile_adds_new_repos() {
+        let mut db = Database::in_memory();
+
+        let summary =
+            reconcile(&mut db, vec![item("prog", "/base/prog"), item("api", "/base/api")]);
+
+        assert_eq!(summary.added, vec!["/base/api", "/base/prog"]);
+        assert!(summary.removed.is_empty());
+        assert_eq!(summary.total, 2);
+    }
+
+    #[test]
+    fn reconcile_keeps_unchanged_records_untouched() {
+        let mut db = db_with(vec![item("prog", "/base/prog")]);
+        let before = db.get_by_path("/base/prog").unwrap();
+
+        let summary = reconcile(&mut db, vec![item("prog", "/base/prog")]);
+
+        assert!(summary.is_empty());
+        let after = db.get_by_path("/base/prog").unwrap();
+        assert_eq!(after.created_at, before.created_at);
+        assert_eq!(after.updated_at, before.updated_at);
+    }
+
+    #[test]
+    fn reconcile_removes_missing_repos() {
+        let mut db = db_with(vec![item("prog", "/base/prog"), item("api", "/base/api")]);
+
+        let summary = reconcile(&mut db, vec![item("prog", "/base/prog")]);
+
+        assert_eq!(summary.removed, vec!["/base/api"]);
+        assert!(db.get_by_path("/base/api").is_none());
+        assert_eq!(summary.total, 1);
+    }
+
+    #[test]
+    fn reconcile_detects_moves_and_keeps_created_at() {
+        let mut db = db_with(vec![item("prog", "/base/prog")]);
+        let before = db.get_by_path("/base/prog").unwrap();
+
+        let summary = reconcile(&mut db, vec![item("prog", "/base/renamed")]);
+
+        assert_eq!(
+            summary.moved,
+            vec![MovedRepo { from: "/base/prog".to_string(), to: "/base/renamed".to_string() }]
+        );
+        assert!(summary.added.is_empty());
+        assert!(summary.removed.is_empty());
+        assert!(db.get_by_path("/base/prog").is_none());
+        let moved = db.get_by_path("/base/renamed").unwrap();
+        assert_eq!(moved.created_at, before.created_at);
+        assert_eq!(moved.full_path, "/base/renamed");
+    }
+
+    #[test]
+    fn reconcile_never_moves_local_repos() {
+        let local = |path: &str| local_item("/base", Path::new(path));
+        let mut db = db_with(vec![local("/base/scratch")]);
+        assert!(db.get_by_path("/base/scratch").unwrap().is_local());
+
+        let summary = reconcile(&mut db, vec![local("/base/other")]);
+
+        assert!(summary.moved.is_empty());
+        assert_eq!(summary.added, vec!["/base/other"]);
+        assert_eq!(summary.removed, vec!["/base/scratch"]);
+        assert_eq!(db.get_by_path("/base/other").unwrap().repo, "other");
+    }
+
+    #[test]
+    fn read_repo_from_dir_records_repos_without_remote() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let repo = temp_dir.path().join("scratch");
+        std::fs::create_dir(&repo).unwrap();
+        std::process::Command::new("git").arg("init").current_dir(&repo).output().unwrap();
+
+        let repos =
+            read_repo_from_dir(temp_dir.path().to_str().unwrap(), &[], 3, &Excludes::default());
+
+        assert_eq!(repos.len(), 1);
+        assert_eq!(repos[0].host, LOCAL_HOST);
+        assert_eq!(repos[0].repo, "scratch");
+        assert!(repos[0].remote_url.is_empty());
+    }
+
+    #[test]
+    fn read_repo_from_dir_skips_excluded_repos() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let base = temp_dir.path();
+        for name in ["api", "vendor/lib", "fixtures/broken", "mirror"] {
+            let repo = base.join(name);
+            std::fs::create_dir_all(&repo).unwrap();
+            let git = |args: &[&str]| {
+                std::process::Command::new("git").args(args).current_dir(&repo).output().unwrap();
+            };
+            git(&["init"]);
+            let owner = if name == "mirror" { "archive" } else { "acme" };
+            git(&[
+                "remote",
+                "add",
+                "origin",
+                &format!("https://github.com/{}/{}.git", owner, name),
+            ]);
+        }
+        std::fs::write(base.join(IGNORE_FILE), "vendor/\n").unwrap();
+
+        let excludes =
+            Excludes::new(&["**/fixtures/**".to_string()], &[], &["archive".to_string()]);
+        let repos = read_repo_from_dir(base.to_str().unwrap(), &[], 3, &excludes);
+
+        let paths: Vec<String> = repos.iter().map(|repo| repo.full_path.clone()).collect();
+        assert_eq!(paths, vec![base.join("api").display().to_string()]);
+    }
+
+    #[test]
+    fn reconcile_never_moves_worktrees() {
+        let mut worktree = item("prog", "/base/prog@fix");
+        worktree.worktree_of = Some("/base/prog".to_string());
+        let mut db = db_with(vec![item("prog", "/base/prog"), worktree]);
+        assert_eq!(
+            db.get_by_path("/base/prog@fix").unwrap().worktree_of.as_deref(),
+            Some("/base/prog")
+        );
+
+        let mut other = item("prog", "/base/prog@feature");
+        other.worktree_of = Some("/base/prog".to_string());
+        let summary = reconcile(&mut db, vec![item("prog", "/base/prog"), other]);
+
+        assert!(summary.moved.is_empty());
+        assert_eq!(summary.added, vec!["/base/prog@feature"]);
+        assert_eq!(summary.removed, vec!["/base/prog@fix"]);
+    }
+
+    #[test]
+    fn reconcile_records_submodules() {
+        let mut db = db_with(vec![item("prog", "/base/prog")]);
+        assert!(db.get_by_path("/base/prog").unwrap().submodules.is_empty());
+
+        let mut with_submodule = item("prog", "/base/prog");
+        with_submodule.submodules = vec![Submodule {
+            name: "vendor/lib".to_string(),
+            path: "vendor/lib".to_string(),
+            url: "../lib".to_string(),
+        }];
+        reconcile(&mut db, vec![with_submodule.clone()]);
+
+        let record = db.get_by_path("/base/prog").unwrap();
+        assert_eq!(record.submodules, with_submodule.submodules);
+    }
+
+    #[test]
+    fn reconcile_scoped_keeps_records_out_of_scope() {
+        let mut db = db_with(vec![item("prog", "/base/prog"), item("api", "/other/api")]);
+
+        let in_base = |path: &str| Path::new(path).starts_with("/base");
+        let summary = reconcile_scoped(&mut db, vec![item("cli", "/base/cli")], in_base);
+
+        assert_eq!(summary.added, vec!["/base/cli"]);
+        assert_eq!(summary.removed, vec!["/base/prog"]);
+        assert!(db.get_by_path("/other/api").is_some());
+    }
+
+    #[test]
+    fn beyond_walk_keeps_deeper_repositories_that_exist() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let base = temp_dir.path().to_string_lossy().to_string();
+        let deep = temp_dir.path().join("gitlab.com/group/sub/api");
+        std::fs::create_dir_all(deep.join(".git")).unwrap();
+        let base_dirs = vec![base.clone()];
+
+        assert!(beyond_walk(&deep.to_string_lossy(), &base_dirs, 3));
+        assert!(!beyond_walk(&deep.to_string_lossy(), &base_dirs, 4));
+        assert!(!beyond_walk(&format!("{}/gitlab.com/group/sub/gone", base), &base_dirs, 3));
+        assert!(!beyond_walk(&format!("{}/github.com/acme/api", base), &base_dirs, 3));
+    }
+
+    #[test]
+    fn reconcile_reports_remote_changes() {
+        let mut db = db_with(vec![item("prog", "/base/prog")]);
+        let before = db.get_by_path("/base/prog").unwrap();
+
+        let mut transferred = item("prog", "/base/prog");
+        transferred.owner = "new-owner".to_string();
+        transferred.remote_url = "https://github.com/new-owner/prog.git".to_string();
+        let summary = reconcile(&mut db, vec![transferred]);
+
+        assert_eq!(summary.remote_changed.len(), 1);
+        assert_eq!(summary.remote_changed[0].from, "https://github.com/user/prog.git");
+        assert_eq!(summary.remote_changed[0].to, "https://github.com/new-owner/prog.git");
+        let after = db.get_by_path("/base/prog").unwrap();
+        assert_eq!(after.owner, "new-owner");
+        assert_eq!(after.created_at, before.created_at);
+    }
 
     #[test]
     fn read_repo_from_missing_dir_returns_empty() {
@@ -137,50 +543,126 @@ mod tests {
         assert!(!missing_dir.exists(), "test path should not exist");
         let repos = read_repo_from_dir(
             missing_dir.to_str().expect("temp path should be valid UTF-8 for this test"),
+            &[],
+            3,
+            &Excludes::default(),
         );
         assert!(repos.is_empty());
     }
 }
 
-pub fn sync(c: &Context, silent: bool) {
-    if !silent {
-        println!("Deleting old database...");
-    }
+pub fn sync(c: &Context, silent: bool) -> SyncSummary {
     let now = Instant::now();
-    c.database_mut().reset();
 
     if !silent {
         println!("Syncing...");
     }
 
     let base_dirs = c.config().base_dirs();
+    let preferred_remotes = &c.config().preferred_remotes;
+    let max_depth = c.config().discovery_depth();
+    let excludes = c.excludes();
 
-    let repos: Vec<SyncItem> =
-        base_dirs.par_iter().map(|base_dir| read_repo_from_dir(base_dir)).flatten().collect();
-
-    for repo in repos {
-        if !silent {
-            println!("Syncing {:?}", repo.full_path);
-        }
-        c.database_mut().record_item(
-            &repo.base_dir,
-            &repo.remote_url,
-            &repo.host,
-            &repo.repo,
-            &repo.owner,
-            &repo.full_path,
-        );
-    }
+    let repos: Vec<SyncItem> = base_dirs
+        .par_iter()
+        .map(|base_dir| read_repo_from_dir(base_dir, preferred_remotes, max_depth, excludes))
+        .flatten()
+        .collect();
 
-    c.database_mut().update_last_sync_time();
-    if let Err(e) = c.database_mut().save() {
+    let mut summary = SyncSummary::default();
+    let result = c.database_mut().update_with_history("sync", |db| {
+        summary = reconcile_scoped(db, repos, |path| !beyond_walk(path, &base_dirs, max_depth));
+        db.update_last_sync_time();
+    });
+    if let Err(e) = result {
         error!("Failed to save database: {}", e);
     }
 
     if !silent {
-        println!("Synced");
+        print_summary(&summary);
         println!("Elapsed {}ms", now.elapsed().as_millis());
     }
+
+    summary
+}
+
+/// Whether the repository at `path` lies deeper below its base dir than the
+/// walk goes, e.g. one of a subgroup cloned by `add`, and is still there.
+/// Such records are kept rather than dropped as missing.
+fn beyond_walk(path: &str, base_dirs: &[String], max_depth: usize) -> bool {
+    let path = Path::new(path);
+    base_dirs.iter().any(|base_dir| {
+        path.strip_prefix(base_dir).is_ok_and(|relative| relative.components().count() > max_depth)
+    }) && path.join(".git").exists()
+}
+
+/// Like [`sync`], but only looks for repositories in and below `roots`, each
+/// given with the base dir it is in. Records elsewhere in the index are kept.
+/// No snapshot is taken: the daemon applies changes often enough to push the
+/// snapshots of user commands out of the history.
+pub(crate) fn sync_paths(c: &Context, roots: &[(String, PathBuf)]) -> SyncSummary {
+    let preferred_remotes = &c.config().preferred_remotes;
+    let max_depth = c.config().discovery_depth();
+    let excludes = c.excludes();
+
+    let repos: Vec<SyncItem> = roots
+        .par_iter()
+        .map(|(base_dir, root)| match root.strip_prefix(base_dir) {
+            Ok(relative) if relative.components().count() <= max_depth => {
+                let depth = max_depth - relative.components().count();
+                walk_repos(base_dir, root, preferred_remotes, depth, excludes)
+            }
+            _ => Vec::new(),
+        })
+        .flatten()
+        .collect();
+    let base_dirs: Vec<String> = roots.iter().map(|(base_dir, _)| base_dir.clone()).collect();
+    let in_scope = |path: &str| {
+        roots.iter().any(|(_, root)| Path::new(path).starts_with(root))
+            && !beyond_walk(path, &base_dirs, max_depth)
+    };
+
+    let mut summary = SyncSummary::default();
+    let result = c.database_mut().update(|db| {
+        summary = reconcile_scoped(db, repos, in_scope);
+        db.update_last_sync_time();
+    });
+    if let Err(e) = result {
+        error!("Failed to save database: {}", e);
+    }
+    summary
+}
+
+fn print_summary(summary: &SyncSummary) {
+    for path in &summary.added {
+        println!("{} {}", "+ added  ".green(), path);
+    }
+    for path in &summary.removed {
+        println!("{} {}", "- removed".red(), path);
+    }
+    for moved in &summary.moved {
+        println!("{} {} -> {}", "> moved  ".blue(), moved.from, moved.to);
+    }
+    for changed in &summary.remote_changed {
+        println!("{} {} ({} -> {})", "~ remote ".yellow(), changed.path, changed.from, changed.to);
+    }
+
+    if summary.is_empty() {
+        println!("{}", format!("Synced {} repositories, no changes.", summary.total).green());
+    } else {
+        println!(
+            "{}",
+            format!(
+                "Synced {} repositories: {} added, {} removed, {} moved, {} remote changed.",
+                summary.total,
+                summary.added.len(),
+                summary.removed.len(),
+                summary.moved.len(),
+                summary.remote_changed.len()
+            )
+            .green()
+        );
+    }
 }
 
 pub fn check_auto_sync(c: &Context) {
@@ -7,28 +7,84 @@ mod internal;
 mod macros;
 
 use crate::cli::{Cli, ECommands};
+use crate::helpers::colors::Colorize;
 
 fn main() {
     // use PROG_LOG="debug" to enable debug logs
     env_logger::Builder::new().parse_env("PROG_LOG").format_timestamp(None).init();
 
+    let cli = Cli::new();
+
+    // Database maintenance works on the index file directly, before the
+    // context loads (and possibly migrates or syncs) it.
+    if let Some(ECommands::Db(db)) = &cli.command {
+        match &db.command {
+            Some(db_cmd) => {
+                if commands::db::run(db_cmd) > 0 {
+                    std::process::exit(1);
+                }
+            }
+            None => {
+                if let Some(mut cmd) = Cli::get_subcommand("db") {
+                    cmd.print_help().expect("Could not print help");
+                }
+            }
+        }
+        return;
+    }
+
     let mut context = context::Context::new();
 
-    let cli = Cli::new();
     match cli.command {
-        Some(ECommands::Add { url, rest }) => commands::add::run(&mut context, &url, &rest),
-        Some(ECommands::Find { keyword, query }) => commands::find::run(&context, &keyword, query),
-        Some(ECommands::Sync) => commands::sync::run(&context),
+        Some(ECommands::Add { url, base, rest }) => {
+            commands::add::run(&mut context, &url, base.as_deref(), &rest)
+        }
+        Some(ECommands::Find { keywords, query, checkout, pick, submodule }) => {
+            let options = commands::find::FindOptions { checkout, choose_again: pick, submodule };
+            commands::find::run(&context, &keywords.join(" "), query, cli.format, options)
+        }
+        Some(ECommands::Forget { keywords }) => {
+            commands::forget::run(&context, &keywords.join(" "))
+        }
+        Some(ECommands::Complete { words }) => {
+            // Subcommands and options are left to the generated completions.
+            let first = words.first().map(String::as_str).unwrap_or_default();
+            if first.starts_with('-') || first == "help" || Cli::get_subcommand(first).is_some() {
+                return;
+            }
+            for candidate in commands::find::complete(&context, &words) {
+                println!("{}", candidate);
+            }
+        }
+        Some(ECommands::Visit { path }) => {
+            let path = std::path::absolute(&path).unwrap_or(path);
+            commands::find::record_visit(&context, &path.to_string_lossy())
+        }
+        Some(ECommands::Sync) => commands::sync::run(&context, cli.format),
         Some(ECommands::Import { path }) => commands::import::run(&mut context, path),
         Some(ECommands::Remove { path, yes }) => commands::remove::run(&mut context, path, yes),
         Some(ECommands::Clean { yes }) => commands::clean::run(&context, yes),
-        Some(ECommands::List) => commands::list::run(&mut context),
-        Some(ECommands::Check { dirty_only, json }) => {
-            let issues = commands::check::run(&mut context, dirty_only, json);
+        Some(ECommands::List { query, tags }) => {
+            commands::list::run(&mut context, &tags, &query.join(" "), cli.format)
+        }
+        Some(ECommands::Check { query, tags, dirty_only, json }) => {
+            let format = if json {
+                eprintln!("{}", "`--json` is deprecated, use `--format json`".yellow());
+                Some(commands::OutputFormat::Json)
+            } else {
+                cli.format
+            };
+            let issues =
+                commands::check::run(&mut context, dirty_only, format, &tags, &query.join(" "));
             if issues > 0 {
                 std::process::exit(1);
             }
         }
+        Some(ECommands::Doctor { fix }) => {
+            if commands::doctor::run(&mut context, fix) > 0 {
+                std::process::exit(1);
+            }
+        }
         Some(ECommands::Tmp(tmp)) => {
             let tmp_cmd = tmp.command;
             if tmp_cmd.is_none() {
@@ -40,9 +96,40 @@ fn main() {
 
                 std::process::exit(1);
             }
-            commands::tmp::run(&mut context, &tmp_cmd.unwrap());
+            commands::tmp::run(&mut context, &tmp_cmd.unwrap(), cli.format);
+        }
+        Some(ECommands::Tag(tag)) => match tag.command {
+            Some(tag_cmd) => commands::tag::run(&mut context, &tag_cmd),
+            None => {
+                if let Some(mut cmd) = Cli::get_subcommand("tag") {
+                    cmd.print_help().expect("Could not print help");
+                }
+            }
+        },
+        Some(ECommands::Note { repo, text, clear }) => {
+            commands::note::run(&mut context, &repo, text.as_deref(), clear)
+        }
+        Some(ECommands::Export) => commands::export::run(&context),
+        Some(ECommands::Apply { path, dry_run }) => {
+            if commands::apply::run(&mut context, &path, dry_run) > 0 {
+                std::process::exit(1);
+            }
+        }
+        Some(ECommands::Wt(wt)) => match wt.command {
+            Some(wt_cmd) => commands::wt::run(&mut context, &wt_cmd),
+            None => {
+                if let Some(mut cmd) = Cli::get_subcommand("wt") {
+                    cmd.print_help().expect("Could not print help");
+                }
+            }
+        },
+        Some(ECommands::Daemon(daemon)) => {
+            if commands::daemon::run(&context, daemon.command.as_ref()) > 0 {
+                std::process::exit(1);
+            }
         }
         Some(ECommands::Shell { shell }) => Cli::activate(shell),
+        Some(ECommands::Db(_)) => unreachable!("handled before the context is created"),
         None => Cli::show_help(),
     }
 }
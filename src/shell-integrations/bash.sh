@@ -8,12 +8,29 @@ function __prog_p() {
     elif [[ "$#" -eq 1 ]] && {
             [[ -d "$1" ]] || [[ "$1" = '-' ]] || [[ "$1" =~ ^[-+][0-9]$ ]]
         }; then
-        __prog_cd "$1"
+        __prog_cd "$1" || return $?
+        \command prog visit -- "$PWD" >/dev/null 2>&1
     elif [[ "$#" -eq 2 ]] && [[ "$1" = "--" ]]; then
         \command prog "$2"
+    elif [[ "$1" = "--checkout" || "$1" = "--pick" || "$1" = "--submodule" ]] && [[ "$#" -ge 2 ]]; then
+        # Pass find options (switch to the branch a forge URL refers to, or
+        # choose again) through and cd there
+        local -a options=()
+        while [[ "$1" = "--checkout" || "$1" = "--pick" || "$1" = "--submodule" ]]; do
+            options+=("$1")
+            shift
+        done
+        local result
+        result="$(\command prog find --query "${options[@]}" -- "$@")" || return $?
+        [[ -n "$result" ]] && __prog_cd "${result}"
     elif [[ "$1" = "add" ]] && [[ "$#" -ge 2 ]]; then
         # Run prog add and then cd to the cloned repo
         local url="$2"
+        if [[ "$2" = "--base" ]]; then
+            url="$4"
+        elif [[ "$2" == --base=* ]]; then
+            url="$3"
+        fi
         \command prog "$@" || return $?
         local result
         result="$(\command prog find --query -- "$url")" || return $?
@@ -31,4 +48,17 @@ function __prog_p() {
 
 function {{command}}() {
   __prog_p "$@"
-}
\ No newline at end of file
+}
+
+# Complete paths inside a repository (`p prog/src/<TAB>`), otherwise fall
+# back to the generated completions.
+function __prog_complete() {
+    mapfile -t COMPREPLY < <(\command prog complete -- "${COMP_WORDS[@]:1:COMP_CWORD}" 2>/dev/null)
+    if [[ "${#COMPREPLY[@]}" -gt 0 ]]; then
+        compopt -o nospace 2>/dev/null
+    else
+        _{{command}} "$@"
+    fi
+}
+
+complete -F __prog_complete -o bashdefault -o default {{command}}
\ No newline at end of file
@@ -18,13 +18,44 @@ function __prog_p {
             ($args[0] -match '^[-+][0-9]$')
         )) {
         __prog_cd $args[0]
+        prog visit -- $PWD.Path *> $null
     }
     elseif ($args.Count -eq 2 -and $args[0] -eq '--') {
         prog $args[1]
     }
+    elseif ($args[0] -in '--checkout', '--pick', '--submodule' -and $args.Count -ge 2) {
+        # Pass find options (switch to the branch a forge URL refers to, or
+        # choose again) through and cd there
+        $count = 0
+        while ($count -lt $args.Count - 1 -and $args[$count] -in '--checkout', '--pick', '--submodule') {
+            $count++
+        }
+        $options = $args[0..($count - 1)]
+        $rest = $args[$count..($args.Count - 1)]
+        $result = $null
+        try {
+            $result = prog find --query @options -- @rest
+            if ($LASTEXITCODE -ne 0) {
+                return $LASTEXITCODE
+            }
+        }
+        catch {
+            return 1
+        }
+
+        if ($result) {
+            __prog_cd $result
+        }
+    }
     elseif ($args[0] -eq 'add' -and $args.Count -ge 2) {
         # Run prog add and then cd to the cloned repo
         $url = $args[1]
+        if ($args[1] -eq '--base') {
+            $url = $args[3]
+        }
+        elseif ($args[1] -like '--base=*') {
+            $url = $args[2]
+        }
         prog @args
         if ($LASTEXITCODE -ne 0) {
             return $LASTEXITCODE
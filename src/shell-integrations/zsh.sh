@@ -10,14 +10,36 @@ function __prog_p() {
                     [[ -d "$1" ]] || [[ "$1" = '-' ]] || [[ "$1" =~ ^[-+][0-9]$ ]]
             }
     then
-            __prog_cd "$1"
+            __prog_cd "$1" || return $?
+            \command prog visit -- "$PWD" >/dev/null 2>&1
     elif [[ "$#" -eq 2 ]] && [[ "$1" = "--" ]]
     then
             \command prog "$2"
+    elif [[ "$1" = "--checkout" || "$1" = "--pick" || "$1" = "--submodule" ]] && [[ "$#" -ge 2 ]]
+    then
+            # Pass find options (switch to the branch a forge URL refers to, or
+            # choose again) through and cd there
+            \builtin local -a options
+            options=()
+            while [[ "$1" = "--checkout" || "$1" = "--pick" || "$1" = "--submodule" ]]
+            do
+                    options+=("$1")
+                    \builtin shift
+            done
+            \builtin local result
+            result="$(\command prog find --query "${options[@]}" -- "$@")" || return $?
+            [[ -n "$result" ]] && __prog_cd "${result}"
     elif [[ "$1" = "add" ]] && [[ "$#" -ge 2 ]]
     then
             # Run prog add and then cd to the cloned repo
             \builtin local url="$2"
+            if [[ "$2" = "--base" ]]
+            then
+                    url="$4"
+            elif [[ "$2" == --base=* ]]
+            then
+                    url="$3"
+            fi
             \command prog "$@" || return $?
             \builtin local result
             result="$(\command prog find --query -- "$url")" || return $?
@@ -37,3 +59,22 @@ function __prog_p() {
 function {{command}}() {
   __prog_p "$@"
 }
+
+# Complete paths inside a repository (`p prog/src/<TAB>`), otherwise fall
+# back to the generated completions.
+function __prog_complete() {
+    \builtin local -a candidates
+    candidates=("${(@f)$(\command prog complete -- "${(@)words[2,CURRENT]}" 2>/dev/null)}")
+    candidates=("${(@)candidates:#}")
+    if (( ${#candidates} ))
+    then
+            compadd -Q -S '' -- "${candidates[@]}"
+    else
+            _{{command}} "$@"
+    fi
+}
+
+if (( $+functions[compdef] ))
+then
+        compdef __prog_complete {{command}}
+fi